@@ -1,19 +1,20 @@
-use std::{
-    io::{stdout, Write},
-    time::Duration,
-};
+use std::{io::stdout, time::Duration};
 
 use cog_core::{
-    runtime::{init, RuntimeMessage},
-    ui::Border,
-    AppMessage,
-};
-use crossterm::{
-    event::{Event, KeyCode, KeyEvent, KeyModifiers},
-    queue,
-    style::{ContentStyle, Stylize},
+    init, restore,
+    runtime::{event_loop, Options, RuntimeMessage},
+    ui::{Panel, Progress},
+    util::Anchor,
+    AppMessage, Model,
 };
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
 use eyre::Result;
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::{Style, Stylize},
+    widgets::Paragraph,
+    Frame,
+};
 
 #[derive(Default)]
 struct MainModel {
@@ -27,7 +28,7 @@ enum Message {
     Increment(u64),
 }
 
-impl cog_core::Model<Message> for MainModel {
+impl Model<Message> for MainModel {
     fn update(&mut self, message: AppMessage<Message>) -> RuntimeMessage<Message> {
         match message {
             AppMessage::Event(event) => {
@@ -56,25 +57,40 @@ impl cog_core::Model<Message> for MainModel {
             }
             AppMessage::App(Message::Increment(amount)) => self.counter += amount,
             AppMessage::Init => self.initialized = true,
+            AppMessage::Panic(..) => (),
         };
 
         RuntimeMessage::Empty
     }
 
-    fn view(&self, mut writer: impl Write) -> Result<()> {
+    fn view(&mut self, frame: &mut Frame) {
         let text = format!(
             "terminal event: {:?}
 delayed count (batched): {}
 initialized: {}",
             self.last_event, self.counter, self.initialized
         );
-        queue!(writer, Border::normal(text, ContentStyle::new().red()))?;
 
-        Ok(())
+        let area = Anchor::default()
+            .percentage_uniform(60)
+            .flex_uniform(Flex::Center)
+            .compute(frame.area());
+        let panel = Panel::new().title("features").style(Style::new().red());
+        panel.render_with(area, frame.buffer_mut(), Paragraph::new(text));
+
+        let [progress] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::End)
+            .areas(panel.inner(area));
+        frame.render_widget(
+            Progress::new((self.counter % 100) as f64 / 100.).label("count"),
+            progress,
+        );
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    init(stdout(), MainModel::default()).await
+    let terminal = init(stdout())?;
+    event_loop(MainModel::default(), terminal, Options::default()).await?;
+    restore()
 }
@@ -0,0 +1,298 @@
+use std::time::Duration;
+
+use ratatui::{layout::Position, style::Color};
+
+use crate::theme;
+
+/// how a tween's progress speeds up and slows down, see <https://easings.net>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+}
+
+impl Easing {
+    /// maps linear progress `t` between 0 and 1 to eased progress, which starts at 0 and ends at 1
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Easing::InOutQuad if t < 0.5 => 2.0 * t * t,
+            Easing::InOutQuad => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Easing::InCubic => t.powi(3),
+            Easing::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::InOutCubic if t < 0.5 => 4.0 * t.powi(3),
+            Easing::InOutCubic => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+        }
+    }
+}
+
+/// values which can be blended, `t` being 0 at `self` and 1 at `to`
+pub trait Lerp {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t as f64
+    }
+}
+
+/// rounded to the nearest cell
+impl Lerp for u16 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (*self as f32).lerp(&(*to as f32), t).round() as u16
+    }
+}
+
+impl<A: Lerp, B: Lerp> Lerp for (A, B) {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        (self.0.lerp(&to.0, t), self.1.lerp(&to.1, t))
+    }
+}
+
+impl Lerp for Position {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Position::new(self.x.lerp(&to.x, t), self.y.lerp(&to.y, t))
+    }
+}
+
+/// blends in rgb, so the result may need to be [degraded](theme::ColorDepth::degrade)
+/// for terminals without true color
+///
+/// the terminal's default color can't be blended, so it switches halfway instead
+impl Lerp for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        match (theme::rgb(*self), theme::rgb(*to)) {
+            (Some(from), Some(to)) => {
+                let channel = |from: u8, to: u8| (from as u16).lerp(&(to as u16), t) as u8;
+                Color::Rgb(
+                    channel(from.0, to.0),
+                    channel(from.1, to.1),
+                    channel(from.2, to.2),
+                )
+            }
+            _ if t < 0.5 => *self,
+            _ => *to,
+        }
+    }
+}
+
+/// moves a value from one point to another over time
+#[derive(Debug, Clone)]
+pub struct Tween<V> {
+    from: V,
+    to: V,
+    duration: Duration,
+    elapsed: Duration,
+    easing: Easing,
+}
+
+impl<V: Lerp + Clone> Tween<V> {
+    pub fn new(from: V, to: V, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: Duration::ZERO,
+            easing: Easing::default(),
+        }
+    }
+
+    /// a tween which has already finished at `value`, to be [retargeted](Self::retarget) later
+    pub fn at(value: V, duration: Duration) -> Self {
+        let mut tween = Self::new(value.clone(), value, duration);
+        tween.elapsed = duration;
+        tween
+    }
+
+    pub fn easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// moves time forward by `delta`, such as [`FrameTiming::delta`](crate::runtime::frame::FrameTiming::delta),
+    /// returning whether the tween is still running
+    pub fn advance(&mut self, delta: Duration) -> bool {
+        self.elapsed = (self.elapsed + delta).min(self.duration);
+        !self.finished()
+    }
+
+    /// linear progress from 0 to 1, before easing
+    pub fn progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+
+        self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn value(&self) -> V {
+        self.from.lerp(&self.to, self.easing.apply(self.progress()))
+    }
+
+    pub fn target(&self) -> &V {
+        &self.to
+    }
+
+    /// heads for `to` from wherever the tween currently is, restarting its duration,
+    /// so a moving target is followed smoothly
+    pub fn retarget(&mut self, to: V) {
+        self.from = self.value();
+        self.to = to;
+        self.elapsed = Duration::ZERO;
+    }
+}
+
+/// a sequence of frames, such as the glyphs of a spinner, each shown for the same time
+#[derive(Debug, Clone)]
+pub struct Sprite<F> {
+    frames: Vec<F>,
+    frame_time: Duration,
+    elapsed: Duration,
+    looping: bool,
+}
+
+impl<F> Sprite<F> {
+    /// panics if there are no frames
+    pub fn new(frames: impl IntoIterator<Item = F>, frame_time: Duration) -> Self {
+        let frames: Vec<_> = frames.into_iter().collect();
+        assert!(!frames.is_empty(), "sprites need at least one frame");
+
+        Self {
+            frames,
+            frame_time,
+            elapsed: Duration::ZERO,
+            looping: true,
+        }
+    }
+
+    /// starts over after the last frame instead of stopping on it, which is the default
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// moves time forward by `delta`, returning whether the sprite is still animating
+    pub fn advance(&mut self, delta: Duration) -> bool {
+        self.elapsed += delta;
+        if self.looping && !self.frame_time.is_zero() {
+            // only the position within the loop matters, so time can't grow forever
+            let cycle = self.frame_time * self.frames.len() as u32;
+            self.elapsed =
+                Duration::from_nanos((self.elapsed.as_nanos() % cycle.as_nanos()) as u64);
+        }
+
+        !self.finished()
+    }
+
+    pub fn index(&self) -> usize {
+        if self.frame_time.is_zero() {
+            return self.frames.len() - 1;
+        }
+
+        let index = (self.elapsed.as_nanos() / self.frame_time.as_nanos()) as usize;
+        index.min(self.frames.len() - 1)
+    }
+
+    pub fn current(&self) -> &F {
+        &self.frames[self.index()]
+    }
+
+    /// whether the last frame has been reached, looping sprites never finish
+    pub fn finished(&self) -> bool {
+        !self.looping && self.index() == self.frames.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn test_easing() {
+        for easing in [
+            Easing::Linear,
+            Easing::InQuad,
+            Easing::OutQuad,
+            Easing::InOutQuad,
+            Easing::InCubic,
+            Easing::OutCubic,
+            Easing::InOutCubic,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0, "{:?} should start at 0", easing);
+            assert_eq!(easing.apply(1.0), 1.0, "{:?} should end at 1", easing);
+        }
+
+        assert!(Easing::InQuad.apply(0.5) < 0.5, "ease in should start slow");
+        assert!(
+            Easing::OutQuad.apply(0.5) > 0.5,
+            "ease out should start fast"
+        );
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut tween = Tween::new(0.0, 10.0, MS * 100);
+        assert!(tween.advance(MS * 50));
+        assert_eq!(tween.value(), 5.0);
+
+        tween.retarget(0.0);
+        assert_eq!(tween.value(), 5.0, "retargeting should start where it was");
+        assert!(!tween.advance(MS * 200), "tween should finish");
+        assert_eq!(tween.value(), 0.0);
+
+        assert!(Tween::at(1.0, MS * 100).finished());
+    }
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(
+            Position::new(0, 10).lerp(&Position::new(10, 0), 0.25),
+            Position::new(3, 8)
+        );
+        assert_eq!(
+            Color::Rgb(0, 0, 0).lerp(&Color::White, 0.5),
+            Color::Rgb(128, 128, 128),
+            "named colors should be blended as rgb"
+        );
+        assert_eq!(Color::Reset.lerp(&Color::Red, 0.25), Color::Reset);
+    }
+
+    #[test]
+    fn test_sprite() {
+        let mut sprite = Sprite::new(['a', 'b', 'c'], MS * 10);
+        sprite.advance(MS * 25);
+        assert_eq!(*sprite.current(), 'c');
+        assert!(sprite.advance(MS * 10), "looping sprites never finish");
+        assert_eq!(*sprite.current(), 'a', "sprite should loop");
+
+        let mut sprite = Sprite::new(['a', 'b'], MS * 10).looping(false);
+        assert!(!sprite.advance(MS * 100));
+        assert_eq!(
+            *sprite.current(),
+            'b',
+            "sprite should stop on its last frame"
+        );
+    }
+}
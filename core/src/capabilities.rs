@@ -0,0 +1,91 @@
+use std::sync::OnceLock;
+
+use crossterm::{event::KeyboardEnhancementFlags, terminal};
+use log::info;
+
+use crate::theme::ColorDepth;
+
+/// what the terminal supports, queried once by [`init`](crate::init) or [`init_inline`](crate::init_inline)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// how many colors can be shown, from the environment as terminals can't be asked
+    pub color: ColorDepth,
+    /// whether the kitty keyboard protocol is supported, which reports key releases and
+    /// tells apart keys such as esc and ctrl+[
+    pub keyboard_enhancement: bool,
+}
+
+impl Default for Capabilities {
+    /// what almost every terminal supports
+    fn default() -> Self {
+        Self {
+            color: ColorDepth::Ansi16,
+            keyboard_enhancement: false,
+        }
+    }
+}
+
+static CAPABILITIES: OnceLock<Capabilities> = OnceLock::new();
+
+impl Capabilities {
+    /// asks the terminal on stdout, which has to be in raw mode, falling back to the defaults
+    /// for anything that it doesn't answer
+    pub fn query() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            color: ColorDepth::detect(),
+            keyboard_enhancement: terminal::supports_keyboard_enhancement()
+                .unwrap_or(defaults.keyboard_enhancement),
+        }
+    }
+
+    /// queries the terminal the first time it's called, later calls keep the first answer
+    pub(crate) fn install() {
+        let capabilities = CAPABILITIES.get_or_init(Self::query);
+        info!("terminal capabilities: {:?}", capabilities);
+    }
+
+    /// the capabilities queried at startup, or what the environment tells if the terminal
+    /// hasn't been set up yet, so themes can be loaded before it is
+    pub fn get() -> Self {
+        CAPABILITIES.get().copied().unwrap_or_else(|| Self {
+            color: ColorDepth::detect(),
+            ..Self::default()
+        })
+    }
+
+    /// the kitty keyboard protocol features to enable, if the terminal supports it
+    ///
+    /// only escape codes are disambiguated, as models expect each key once and reporting
+    /// releases or repeats would send them more
+    pub fn keyboard_flags(&self) -> Option<KeyboardEnhancementFlags> {
+        self.keyboard_enhancement
+            .then_some(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = Capabilities::get();
+        assert_eq!(
+            capabilities.color,
+            ColorDepth::detect(),
+            "color depth should come from the environment before the terminal is set up"
+        );
+        assert_eq!(capabilities.keyboard_flags(), None);
+
+        let enhanced = Capabilities {
+            keyboard_enhancement: true,
+            ..capabilities
+        };
+        assert_eq!(
+            enhanced.keyboard_flags(),
+            Some(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        );
+    }
+}
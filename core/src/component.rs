@@ -0,0 +1,184 @@
+use crossterm::event::Event;
+
+use crate::{runtime::RuntimeMessage, AppMessage, Model};
+
+/// a model which may decline events, letting them bubble up to its parent
+pub trait Component<T: 'static>: Model<T> {
+    /// offers an event to the component, returning `None` if it was not handled
+    fn event(&mut self, event: &Event) -> Option<RuntimeMessage<T>> {
+        Some(self.update(AppMessage::Event(event.clone())))
+    }
+}
+
+/// tracks which child of a component is focused
+///
+/// children are traversed in order, and screens pushed on top take focus until they are popped
+pub struct Focus<K> {
+    order: Vec<K>,
+    current: usize,
+    stack: Vec<K>,
+}
+
+impl<K: Copy + PartialEq> Focus<K> {
+    pub fn new(order: impl IntoIterator<Item = K>) -> Self {
+        Self {
+            order: order.into_iter().collect(),
+            current: 0,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn focused(&self) -> Option<K> {
+        self.stack
+            .last()
+            .or_else(|| self.order.get(self.current))
+            .copied()
+    }
+
+    pub fn is_focused(&self, key: K) -> bool {
+        self.focused() == Some(key)
+    }
+
+    /// focuses a child in the traversal order, returning false if it isn't part of it
+    pub fn focus(&mut self, key: K) -> bool {
+        match self.order.iter().position(|k| *k == key) {
+            Some(i) => {
+                self.current = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// moves focus to the next child, wrapping around
+    ///
+    /// traversal is disabled while a screen is pushed
+    pub fn next(&mut self) {
+        if self.stack.is_empty() && !self.order.is_empty() {
+            self.current = (self.current + 1) % self.order.len();
+        }
+    }
+
+    /// moves focus to the previous child, wrapping around
+    pub fn prev(&mut self) {
+        if self.stack.is_empty() && !self.order.is_empty() {
+            self.current = (self.current + self.order.len() - 1) % self.order.len();
+        }
+    }
+
+    /// stacks a screen on top, taking focus until it is popped
+    pub fn push(&mut self, key: K) {
+        self.stack.push(key);
+    }
+
+    pub fn pop(&mut self) -> Option<K> {
+        self.stack.pop()
+    }
+}
+
+/// offers an event to the focused child only, returning `None` if nothing handled it
+///
+/// `Init` and `App` messages should still be routed with [`passthru!`](crate::passthru)
+#[macro_export]
+macro_rules! route {
+    ($event:expr, $focused:expr, $(($key:pat, $path:path, $model:expr)), *) => {
+        match $focused {
+            $(Some($key) => $crate::component::Component::event(&mut $model, $event).map(|msg| msg.map($path)),)*
+            _ => None,
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use ratatui::Frame;
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Screen {
+        World,
+        Inventory,
+        Menu,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Child(u8),
+    }
+
+    /// handles a single key, letting everything else bubble
+    struct Child(char);
+
+    impl Model<u8> for Child {
+        fn update(&mut self, _message: AppMessage<u8>) -> RuntimeMessage<u8> {
+            RuntimeMessage::App(AppMessage::App(self.0 as u8))
+        }
+
+        fn view(&mut self, _frame: &mut Frame) {}
+    }
+
+    impl Component<u8> for Child {
+        fn event(&mut self, event: &Event) -> Option<RuntimeMessage<u8>> {
+            match event {
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(c),
+                    ..
+                }) if *c == self.0 => Some(self.update(AppMessage::Event(event.clone()))),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_traversal() {
+        let mut focus = Focus::new([Screen::World, Screen::Inventory]);
+        assert_eq!(focus.focused(), Some(Screen::World));
+
+        focus.next();
+        assert_eq!(focus.focused(), Some(Screen::Inventory));
+        focus.next();
+        assert_eq!(focus.focused(), Some(Screen::World), "focus should wrap");
+        focus.prev();
+        assert_eq!(focus.focused(), Some(Screen::Inventory));
+
+        focus.push(Screen::Menu);
+        focus.next();
+        assert_eq!(
+            focus.focused(),
+            Some(Screen::Menu),
+            "pushed screen should keep focus"
+        );
+        assert_eq!(focus.pop(), Some(Screen::Menu));
+        assert_eq!(focus.focused(), Some(Screen::Inventory));
+
+        assert!(!focus.focus(Screen::Menu), "menu isn't traversable");
+    }
+
+    #[test]
+    fn test_route() {
+        let focus = Focus::new([Screen::World, Screen::Inventory]);
+        let mut world = Child('w');
+        let mut inventory = Child('i');
+
+        let route = |event: &Event, world: &mut Child, inventory: &mut Child| {
+            route!(
+                event,
+                focus.focused(),
+                (Screen::World, Message::Child, *world),
+                (Screen::Inventory, Message::Child, *inventory)
+            )
+        };
+
+        let key = |c| Event::Key(KeyEvent::from(KeyCode::Char(c)));
+        assert!(matches!(
+            route(&key('w'), &mut world, &mut inventory),
+            Some(RuntimeMessage::App(AppMessage::App(Message::Child(b'w'))))
+        ));
+        assert!(
+            route(&key('i'), &mut world, &mut inventory).is_none(),
+            "unfocused child shouldn't receive events, and unhandled ones should bubble"
+        );
+    }
+}
@@ -2,21 +2,34 @@ use std::{
     fmt::Debug,
     io::{stdout, Write},
     panic,
+    sync::atomic::{AtomicBool, Ordering},
 };
 
-use crossterm::{event, execute, terminal};
+use crossterm::{cursor, event, execute, terminal};
 use eyre::Result;
 use log::{error, info};
-use ratatui::{prelude::CrosstermBackend, Frame, Terminal};
+use ratatui::{prelude::CrosstermBackend, Frame, Terminal, TerminalOptions, Viewport};
 
-use runtime::RuntimeMessage;
+use capabilities::Capabilities;
+use runtime::{frame::FrameTiming, task::TaskId, RuntimeMessage};
 
+pub mod animation;
+pub mod capabilities;
+pub mod component;
 pub mod runtime;
+pub mod testing;
+pub mod theme;
+pub mod ui;
 pub mod util;
 
-pub trait Model<T: Send + 'static> {
+pub trait Model<T: 'static> {
     fn update(&mut self, message: AppMessage<T>) -> RuntimeMessage<T>;
     fn view(&mut self, frame: &mut Frame);
+
+    /// called before each redraw, returning true requests another frame after this one
+    fn on_frame(&mut self, _timing: &FrameTiming) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -24,24 +37,94 @@ pub enum AppMessage<T> {
     Event(event::Event),
     App(T),
     Init,
+    /// a task panicked, with its id if it was keyed
+    Panic(Option<TaskId>, String),
+}
+
+/// whether the terminal was set up by [`init_inline`], which leaves the main screen in place
+static INLINE: AtomicBool = AtomicBool::new(false);
+
+/// turns on the keyboard enhancements the terminal supports, see [`Capabilities::keyboard_flags`]
+fn enhance_keyboard(mut writer: impl Write) -> Result<()> {
+    if let Some(flags) = Capabilities::get().keyboard_flags() {
+        execute!(writer, event::PushKeyboardEnhancementFlags(flags))?;
+    }
+
+    Ok(())
 }
 
 pub fn restore() -> Result<()> {
     info!("restoring terminal");
 
+    if Capabilities::get().keyboard_flags().is_some() {
+        execute!(stdout(), event::PopKeyboardEnhancementFlags)?;
+    }
     terminal::disable_raw_mode()?;
     execute!(
         stdout(),
-        terminal::LeaveAlternateScreen,
-        event::DisableMouseCapture
+        event::DisableBracketedPaste,
+        event::DisableFocusChange
+    )?;
+    if INLINE.load(Ordering::Relaxed) {
+        execute!(stdout(), cursor::Show)?;
+    } else {
+        execute!(
+            stdout(),
+            terminal::LeaveAlternateScreen,
+            event::DisableMouseCapture
+        )?;
+    }
+
+    Ok(())
+}
+
+/// sets the terminal back up after [`restore`], as [`init`] or [`init_inline`] did
+pub fn resume() -> Result<()> {
+    info!("resuming terminal");
+
+    terminal::enable_raw_mode()?;
+    execute!(
+        stdout(),
+        event::EnableBracketedPaste,
+        event::EnableFocusChange
     )?;
+    if !INLINE.load(Ordering::Relaxed) {
+        execute!(
+            stdout(),
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture
+        )?;
+    }
+    enhance_keyboard(stdout())?;
 
     Ok(())
 }
 
+/// restores the terminal and stops the process, resuming once it's continued, such as by `fg`
+pub fn suspend() -> Result<()> {
+    restore()?;
+
+    info!("suspending");
+    // SIGSTOP can't be caught, unlike the SIGTSTP the runtime listens for
+    #[cfg(unix)]
+    // SAFETY: raise only sends a signal to the calling thread, and SIGSTOP's action is fixed
+    // by the kernel, so no handler can run while anything here is borrowed
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+
+    resume()
+}
+
 fn panic_hook() {
     let hook = panic::take_hook();
     panic::set_hook(Box::new(move |info| {
+        // task panics are reported to the model, so the terminal is left alone
+        if runtime::task::catching() {
+            error!("task {}", info);
+            return;
+        }
+
         if let Err(err) = restore() {
             error!("could not restore terminal: {}", err);
         }
@@ -58,8 +141,38 @@ pub fn init<T: Write>(mut writer: T) -> Result<Terminal<CrosstermBackend<T>>> {
     execute!(
         writer,
         terminal::EnterAlternateScreen,
-        event::EnableMouseCapture
+        event::EnableMouseCapture,
+        event::EnableBracketedPaste,
+        event::EnableFocusChange
     )?;
+    Capabilities::install();
+    enhance_keyboard(&mut writer)?;
 
     Ok(Terminal::new(CrosstermBackend::new(writer))?)
 }
+
+/// like [`init`], but draws into `height` lines below the cursor instead of taking over the screen,
+/// leaving the last frame in the scrollback once the model exits
+///
+/// mouse capture isn't enabled, so the terminal can still be scrolled and selected from
+pub fn init_inline<T: Write>(mut writer: T, height: u16) -> Result<Terminal<CrosstermBackend<T>>> {
+    info!("initializing cog inline");
+
+    INLINE.store(true, Ordering::Relaxed);
+    panic_hook();
+    terminal::enable_raw_mode()?;
+    execute!(
+        writer,
+        event::EnableBracketedPaste,
+        event::EnableFocusChange
+    )?;
+    Capabilities::install();
+    enhance_keyboard(&mut writer)?;
+
+    Ok(Terminal::with_options(
+        CrosstermBackend::new(writer),
+        TerminalOptions {
+            viewport: Viewport::Inline(height),
+        },
+    )?)
+}
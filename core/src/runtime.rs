@@ -1,28 +1,60 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, io, path::PathBuf, sync::atomic::Ordering, time::Duration};
 
-use crossterm::{event, terminal};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers};
 use eyre::Result;
+use frame::FrameClock;
 use futures::{
-    channel::mpsc::{self, SendError},
-    future::BoxFuture,
-    stream::{iter, select},
-    SinkExt, StreamExt,
+    future::{pending, ready, LocalBoxFuture},
+    stream::LocalBoxStream,
+    stream::{empty, iter, select, unfold},
+    Stream, StreamExt,
 };
-use log::{error, trace};
-use ratatui::DefaultTerminal;
+use log::{error, trace, warn};
+use queue::Queue;
+use ratatui::{backend::Backend, Terminal};
+use replay::Recorder;
+use subscription::{Scheduler, Subscription, SubscriptionId};
+use task::{TaskId, Tasks};
+use tokio::time::{interval, sleep_until, Instant, Interval, MissedTickBehavior};
 
 use crate::{AppMessage, Model};
 
+pub mod clock;
+pub mod frame;
+pub mod headless;
+pub mod queue;
+pub mod remote;
+pub mod replay;
+pub mod subscription;
+pub mod task;
+
 pub enum RuntimeMessage<T> {
     Empty,
     Exit,
     Batch(Vec<RuntimeMessage<T>>),
-    Task(BoxFuture<'static, RuntimeMessage<T>>),
+    Task(LocalBoxFuture<'static, RuntimeMessage<T>>),
+    /// a task which replaces any running task with the same id
+    Keyed(TaskId, LocalBoxFuture<'static, RuntimeMessage<T>>),
+    Cancel(TaskId),
+    /// a long running source of messages, each delivered as it is yielded
+    Stream(LocalBoxStream<'static, RuntimeMessage<T>>),
+    /// a stream which replaces any running task or stream with the same id
+    KeyedStream(TaskId, LocalBoxStream<'static, RuntimeMessage<T>>),
     App(AppMessage<T>),
+    /// registers a subscription, replacing any existing one with the same id
+    Subscribe(SubscriptionId, Subscription<T>),
+    Unsubscribe(SubscriptionId),
+    /// changes the period of a subscription, restarting it from now
+    Rerate(SubscriptionId, Duration),
+    /// redraws immediately, bypassing the frame limiter
+    Redraw,
+    /// restores the terminal and stops the process until it's continued,
+    /// then redraws everything and sends the model a resize
+    Suspend,
 }
 
 impl<T: 'static> RuntimeMessage<T> {
-    pub fn map<U>(self, f: impl FnOnce(T) -> U + Send + 'static + Clone) -> RuntimeMessage<U> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U + 'static + Clone) -> RuntimeMessage<U> {
         match self {
             RuntimeMessage::Exit => RuntimeMessage::Exit,
             RuntimeMessage::Empty => RuntimeMessage::Empty,
@@ -32,76 +64,505 @@ impl<T: 'static> RuntimeMessage<T> {
             RuntimeMessage::Task(task) => {
                 RuntimeMessage::Task(Box::pin(async move { task.await.map(f) }))
             }
+            RuntimeMessage::Keyed(id, task) => {
+                RuntimeMessage::Keyed(id, Box::pin(async move { task.await.map(f) }))
+            }
+            RuntimeMessage::Cancel(id) => RuntimeMessage::Cancel(id),
+            RuntimeMessage::Stream(stream) => {
+                RuntimeMessage::Stream(Box::pin(stream.map(move |msg| msg.map(f.clone()))))
+            }
+            RuntimeMessage::KeyedStream(id, stream) => {
+                RuntimeMessage::KeyedStream(id, Box::pin(stream.map(move |msg| msg.map(f.clone()))))
+            }
             RuntimeMessage::App(msg) => RuntimeMessage::App(match msg {
                 AppMessage::Init => AppMessage::Init,
                 AppMessage::Event(event) => AppMessage::Event(event),
                 AppMessage::App(msg) => AppMessage::App(f(msg)),
+                AppMessage::Panic(id, message) => AppMessage::Panic(id, message),
             }),
+            RuntimeMessage::Subscribe(id, subscription) => {
+                RuntimeMessage::Subscribe(id, subscription.map(f))
+            }
+            RuntimeMessage::Unsubscribe(id) => RuntimeMessage::Unsubscribe(id),
+            RuntimeMessage::Rerate(id, period) => RuntimeMessage::Rerate(id, period),
+            RuntimeMessage::Redraw => RuntimeMessage::Redraw,
+            RuntimeMessage::Suspend => RuntimeMessage::Suspend,
         }
     }
 }
 
-pub async fn event_loop<T: Debug + Send + 'static>(
+#[derive(Clone)]
+pub struct Options {
+    fps: Option<u32>,
+    mouse: bool,
+    record: Option<PathBuf>,
+    suspend: bool,
+    queue: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            fps: Some(60),
+            mouse: false,
+            record: None,
+            suspend: true,
+            queue: 1024,
+        }
+    }
+}
+
+impl Options {
+    /// limits how often the model is redrawn, `None` redraws after every message
+    pub fn fps(mut self, fps: Option<u32>) -> Self {
+        self.fps = fps;
+        self
+    }
+
+    /// forwards mouse events to the model instead of dropping them
+    pub fn mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// records every event delivered to the model to `path`, see [`replay`](replay::replay)
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record = Some(path.into());
+        self
+    }
+
+    /// suspends on ctrl+z or SIGTSTP like a normal program would outside of raw mode,
+    /// leaving ctrl+z to the model and suspending only on [`RuntimeMessage::Suspend`] if disabled
+    ///
+    /// only [`event_loop`] drives the local terminal, so it's the only one which ever suspends
+    pub fn suspend(mut self, suspend: bool) -> Self {
+        self.suspend = suspend;
+        self
+    }
+
+    /// how many messages can wait for the model before tasks and streams have to wait for room,
+    /// see [`QueueMetrics`](queue::QueueMetrics)
+    pub fn queue(mut self, capacity: usize) -> Self {
+        self.queue = capacity;
+        self
+    }
+}
+
+/// SIGTSTP, sent by `kill -TSTP` or a terminal which isn't in raw mode
+#[cfg(unix)]
+fn stop_signals() -> Result<LocalBoxStream<'static, ()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let signals = signal(SignalKind::from_raw(libc::SIGTSTP))?;
+    Ok(unfold(signals, |mut signals| async move {
+        signals.recv().await.map(|_| ((), signals))
+    })
+    .boxed_local())
+}
+
+#[cfg(not(unix))]
+fn stop_signals() -> Result<LocalBoxStream<'static, ()>> {
+    Ok(futures::stream::empty().boxed_local())
+}
+
+/// waits for the frame limiter, never resolving if there is none
+async fn next_frame(frames: &mut Option<Interval>) {
+    match frames {
+        Some(frames) => {
+            frames.tick().await;
+        }
+        None => pending().await,
+    }
+}
+
+/// draws the model, returning whether it requested another frame
+fn draw<T: 'static, B: Backend>(
+    terminal: &mut Terminal<B>,
+    model: &mut impl Model<T>,
+    clock: &mut FrameClock,
+    queue: &Queue<T>,
+) -> Result<bool> {
+    let mut timing = clock.next(Instant::now());
+    timing.queue = queue.metrics();
+    trace!(
+        "frame: {}, delta: {:?}, queue: {:?}",
+        timing.frame,
+        timing.delta,
+        timing.queue
+    );
+
+    let animating = model.on_frame(&timing);
+    terminal.draw(|frame| model.view(frame))?;
+
+    Ok(animating)
+}
+
+/// runs the model on `terminal` until it exits, reading events from stdin
+///
+/// everything runs on the current thread inside a [`LocalSet`](tokio::task::LocalSet),
+/// so models, tasks and streams don't need to be `Send`
+pub async fn event_loop<T: Debug + 'static, B: Backend>(
+    model: impl Model<T>,
+    terminal: Terminal<B>,
+    options: Options,
+) -> Result<()> {
+    tokio::task::LocalSet::new()
+        .run_until(run(model, terminal, EventStream::new(), options, true))
+        .await
+}
+
+/// runs the model on `terminal` until it exits, reading events from `events`,
+/// such as input parsed from a pty or socket
+///
+/// the model exits once `events` ends, and as the events may not be from this process's
+/// terminal, ctrl+z and [`RuntimeMessage::Suspend`] never suspend it
+pub async fn event_loop_with<T: Debug + 'static, B: Backend>(
+    model: impl Model<T>,
+    terminal: Terminal<B>,
+    events: impl Stream<Item = io::Result<Event>> + 'static,
+    options: Options,
+) -> Result<()> {
+    tokio::task::LocalSet::new()
+        .run_until(run(model, terminal, events, options, false))
+        .await
+}
+
+async fn run<T: Debug + 'static, B: Backend>(
     mut model: impl Model<T>,
-    mut terminal: DefaultTerminal,
+    mut terminal: Terminal<B>,
+    events: impl Stream<Item = io::Result<Event>> + 'static,
+    options: Options,
+    local: bool,
 ) -> Result<()> {
-    let (mut msg_tx, msgs) = mpsc::unbounded();
-    let msgs = msgs.map(|msg| Ok(msg));
-    let events =
-        event::EventStream::new().map(|e| e.map(|e| RuntimeMessage::App(AppMessage::Event(e))));
-    let mut combined = select(msgs, events);
-
-    msg_tx.send(RuntimeMessage::App(AppMessage::Init)).await?;
-
-    let (c, r) = terminal::size()?;
-    msg_tx
-        .send(RuntimeMessage::App(AppMessage::Event(
-            event::Event::Resize(c, r),
-        )))
-        .await?;
-
-    let mut frame: usize = 0;
-    while let Some(msg) = combined.next().await {
-        let msg = match msg {
-            Err(err) => {
-                error!("message error: {}", err);
-                continue;
+    let queue = Queue::new(options.queue);
+
+    let size = terminal.size()?;
+    let (c, r) = (size.width, size.height);
+    let mut recorder = options
+        .record
+        .map(|path| Recorder::create(path, (c, r)))
+        .transpose()?;
+
+    let (mouse, suspend) = (options.mouse, local && options.suspend);
+    let events = events
+        .boxed_local()
+        .filter(move |e| ready(mouse || !matches!(e, Ok(Event::Mouse(_)))))
+        .map(move |e| match e {
+            Ok(Event::Key(KeyEvent {
+                code: KeyCode::Char('z'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            })) if suspend => Ok(RuntimeMessage::Suspend),
+            e => e.map(|e| RuntimeMessage::App(AppMessage::Event(e))),
+        })
+        .inspect(move |msg| {
+            if let (Some(recorder), Ok(RuntimeMessage::App(AppMessage::Event(e)))) =
+                (recorder.as_mut(), msg)
+                && let Err(err) = recorder.record(e)
+            {
+                error!("could not record event: {}", err);
             }
-            Ok(msg) => msg,
-        };
+        })
+        // the input is gone, such as a closed socket, so nothing can reach the model anymore
+        .chain(iter([Ok(RuntimeMessage::Exit)]));
+    let stops = if suspend {
+        stop_signals()?
+    } else {
+        empty().boxed_local()
+    };
+    let mut input = select(events, stops.map(|_| Ok(RuntimeMessage::Suspend)));
+
+    queue.push(RuntimeMessage::App(AppMessage::Init));
+    queue.push(RuntimeMessage::App(AppMessage::Event(Event::Resize(c, r))));
 
-        if let RuntimeMessage::App(AppMessage::Event(event::Event::Mouse(_))) = msg {
-            continue;
+    let mut frames = options.fps.map(|fps| {
+        let mut frames = interval(Duration::from_secs(1) / fps.max(1));
+        frames.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        frames
+    });
+
+    let mut scheduler = Scheduler::default();
+    let mut tasks = Tasks::default();
+    let mut clock = FrameClock::new();
+    let mut dirty = false;
+    loop {
+        if dirty && frames.is_none() {
+            dirty = draw(&mut terminal, &mut model, &mut clock, &queue)?;
         }
 
+        let deadline = scheduler.deadline();
+        // polled in order, so input is handled before anything the model queued
+        let msg = tokio::select! {
+            biased;
+            msg = input.next() => match msg {
+                Some(Ok(msg)) => msg,
+                Some(Err(err)) => {
+                    error!("message error: {}", err);
+                    continue;
+                }
+                None => break,
+            },
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                // each subscription fires at most once, so this can't grow the queue forever
+                for msg in scheduler.fire(Instant::now()) {
+                    queue.push(RuntimeMessage::App(AppMessage::App(msg)));
+                }
+                continue;
+            }
+            _ = next_frame(&mut frames), if dirty => {
+                dirty = draw(&mut terminal, &mut model, &mut clock, &queue)?;
+                continue;
+            }
+            msg = queue.recv() => msg,
+        };
+
         match msg {
             RuntimeMessage::Exit => break,
             RuntimeMessage::Empty => (),
-            RuntimeMessage::Batch(msgs) => {
-                let mut msg_tx = &msg_tx;
-                iter(msgs.into_iter())
-                    .fold(Ok(()), |acc: Result<_, SendError>, x| async move {
-                        if let Err(_) = acc {
-                            acc
-                        } else {
-                            msg_tx.send(x).await.map(|_| ())
-                        }
-                    })
-                    .await?;
-            }
+            RuntimeMessage::Batch(msgs) => msgs.into_iter().for_each(|msg| queue.push(msg)),
             RuntimeMessage::App(msg) => {
-                trace!("frame: {}, application msg: {:?}", frame, msg);
-                frame = frame.wrapping_add(1);
+                trace!("application msg: {:?}", msg);
 
                 let out_msg = model.update(msg);
-                msg_tx.send(out_msg).await?;
-                terminal.draw(|frame| model.view(frame))?;
+                if !matches!(out_msg, RuntimeMessage::Empty) {
+                    queue.push(out_msg);
+                }
+                dirty = true;
             }
             RuntimeMessage::Task(task) => {
-                let mut msg_tx = msg_tx.clone();
-                tokio::task::spawn(async move { msg_tx.send(task.await).await });
+                let task = tasks.prepare(None, task);
+                let queue = queue.clone();
+                tokio::task::spawn_local(async move { queue.send(task.await).await });
+            }
+            RuntimeMessage::Keyed(id, task) => {
+                let task = tasks.prepare(Some(id), task);
+                let queue = queue.clone();
+                tokio::task::spawn_local(async move { queue.send(task.await).await });
+            }
+            RuntimeMessage::Cancel(id) => tasks.cancel(id),
+            RuntimeMessage::Stream(stream) => {
+                let mut stream = tasks.prepare_stream(None, stream);
+                let queue = queue.clone();
+                tokio::task::spawn_local(async move {
+                    while let Some(msg) = stream.next().await {
+                        queue.send(msg).await;
+                    }
+                });
+            }
+            RuntimeMessage::KeyedStream(id, stream) => {
+                let mut stream = tasks.prepare_stream(Some(id), stream);
+                let queue = queue.clone();
+                tokio::task::spawn_local(async move {
+                    while let Some(msg) = stream.next().await {
+                        queue.send(msg).await;
+                    }
+                });
+            }
+            RuntimeMessage::Subscribe(id, subscription) => {
+                scheduler.subscribe(id, subscription, Instant::now())
+            }
+            RuntimeMessage::Unsubscribe(id) => scheduler.unsubscribe(id),
+            RuntimeMessage::Rerate(id, period) => scheduler.rerate(id, period, Instant::now()),
+            RuntimeMessage::Redraw => dirty = draw(&mut terminal, &mut model, &mut clock, &queue)?,
+            RuntimeMessage::Suspend if !local => {
+                warn!("not suspending, as the terminal being driven isn't this process's")
+            }
+            RuntimeMessage::Suspend => {
+                crate::suspend()?;
+
+                // the screen was left while stopped and the terminal may have been resized
+                terminal.clear()?;
+                let size = terminal.size()?;
+                queue.push(RuntimeMessage::App(AppMessage::Event(Event::Resize(
+                    size.width,
+                    size.height,
+                ))));
             }
         };
     }
+
+    // the final frame is what an inline viewport leaves in the scrollback, so it has to be current,
+    // and the cursor is moved below it so whatever runs next doesn't draw over it
+    if local && crate::INLINE.load(Ordering::Relaxed) {
+        draw(&mut terminal, &mut model, &mut clock, &queue)?;
+        let area = terminal.get_frame().area();
+        terminal.set_cursor_position((0, area.bottom().saturating_sub(1)))?;
+        terminal.backend_mut().append_lines(1)?;
+        Backend::flush(terminal.backend_mut())?;
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crossterm::event::{KeyCode, KeyEvent};
+    use ratatui::{backend::TestBackend, Frame};
+
+    use futures::stream;
+
+    use super::*;
+
+    /// records keys pressed until q, asking to suspend on s
+    #[derive(Default, Clone)]
+    struct Keys(Rc<RefCell<Vec<KeyCode>>>);
+
+    impl Model<()> for Keys {
+        fn update(&mut self, message: AppMessage<()>) -> RuntimeMessage<()> {
+            match message {
+                AppMessage::Event(Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    ..
+                })) => RuntimeMessage::Exit,
+                AppMessage::Event(Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    ..
+                })) => RuntimeMessage::Suspend,
+                AppMessage::Event(Event::Key(key)) => {
+                    self.0.borrow_mut().push(key.code);
+                    RuntimeMessage::Empty
+                }
+                _ => RuntimeMessage::Empty,
+            }
+        }
+
+        fn view(&mut self, frame: &mut Frame) {
+            frame.render_widget(format!("{}", self.0.borrow().len()), frame.area());
+        }
+    }
+
+    fn key(c: char) -> io::Result<Event> {
+        Ok(Event::Key(KeyEvent::from(KeyCode::Char(c))))
+    }
+
+    #[tokio::test]
+    async fn test_event_source() {
+        let model = Keys::default();
+        let terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+        event_loop_with(
+            model.clone(),
+            terminal,
+            iter([key('a'), key('b'), key('q')]).chain(stream::pending()),
+            Options::default().fps(None),
+        )
+        .await
+        .unwrap();
+        assert_eq!(*model.0.borrow(), [KeyCode::Char('a'), KeyCode::Char('b')]);
+
+        let model = Keys::default();
+        let terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+        event_loop_with(
+            model.clone(),
+            terminal,
+            iter([key('a')]),
+            Options::default(),
+        )
+        .await
+        .expect("the model should exit once its events end");
+        assert_eq!(*model.0.borrow(), [KeyCode::Char('a')]);
+    }
+
+    /// floods itself with messages until it has handled a thousand,
+    /// replying to each with a focus event it counts
+    #[derive(Default, Clone)]
+    struct Flood(Rc<RefCell<queue::QueueMetrics>>, usize, Rc<RefCell<usize>>);
+
+    impl Model<()> for Flood {
+        fn update(&mut self, message: AppMessage<()>) -> RuntimeMessage<()> {
+            let reply = || RuntimeMessage::App(AppMessage::Event(Event::FocusGained));
+            match message {
+                AppMessage::Init => RuntimeMessage::Stream(
+                    stream::repeat_with(|| RuntimeMessage::App(AppMessage::App(()))).boxed_local(),
+                ),
+                AppMessage::App(()) => {
+                    self.1 += 1;
+                    if self.1 == 1000 {
+                        RuntimeMessage::Batch(vec![reply(), RuntimeMessage::Exit])
+                    } else {
+                        RuntimeMessage::Batch(vec![reply()])
+                    }
+                }
+                AppMessage::Event(Event::FocusGained) => {
+                    *self.2.borrow_mut() += 1;
+                    RuntimeMessage::Empty
+                }
+                _ => RuntimeMessage::Empty,
+            }
+        }
+
+        fn view(&mut self, _frame: &mut Frame) {}
+
+        fn on_frame(&mut self, timing: &frame::FrameTiming) -> bool {
+            *self.0.borrow_mut() = timing.queue;
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backpressure() {
+        let model = Flood::default();
+        let terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+        event_loop_with(
+            model.clone(),
+            terminal,
+            stream::pending(),
+            Options::default().fps(None).queue(16),
+        )
+        .await
+        .unwrap();
+
+        let metrics = *model.0.borrow();
+        assert!(metrics.peak < 32, "queue should be bounded: {:?}", metrics);
+        assert!(metrics.delayed > 0, "the stream should wait for room");
+        assert!(
+            *model.2.borrow() >= 1000,
+            "replies the model asked for shouldn't be dropped"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suspend_disabled() {
+        let model = Keys::default();
+        let terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+        let ctrl_z = Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        event_loop_with(
+            model.clone(),
+            terminal,
+            iter([Ok(ctrl_z)]),
+            Options::default().suspend(false),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            *model.0.borrow(),
+            [KeyCode::Char('z')],
+            "ctrl+z should reach the model when suspending is disabled"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_suspend_not_local() {
+        let model = Keys::default();
+        let terminal = Terminal::new(TestBackend::new(10, 1)).unwrap();
+        let key = |code, modifiers| Ok(Event::Key(KeyEvent::new(code, modifiers)));
+        event_loop_with(
+            model.clone(),
+            terminal,
+            iter([
+                key(KeyCode::Char('z'), KeyModifiers::CONTROL),
+                key(KeyCode::Char('s'), KeyModifiers::NONE),
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+            ]),
+            Options::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            *model.0.borrow(),
+            [KeyCode::Char('z'), KeyCode::Char('a')],
+            "only the local terminal should be suspended"
+        );
+    }
+}
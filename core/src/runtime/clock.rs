@@ -0,0 +1,161 @@
+use std::{
+    cell::RefCell,
+    future::poll_fn,
+    rc::Rc,
+    task::{Poll, Waker},
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+struct State {
+    now: Instant,
+    sleepers: Vec<(Instant, Waker)>,
+}
+
+thread_local! {
+    /// the virtual clock installed on this thread, time is real if there is none
+    static VIRTUAL: RefCell<Option<Rc<RefCell<State>>>> = const { RefCell::new(None) };
+}
+
+fn installed() -> Option<Rc<RefCell<State>>> {
+    VIRTUAL.with_borrow(|state| state.clone())
+}
+
+/// the current time, virtual if a [`VirtualClock`] is installed
+///
+/// models should use this instead of [`Instant::now`] so they can be tested headlessly
+pub fn now() -> Instant {
+    match installed() {
+        Some(state) => state.borrow().now,
+        None => Instant::now(),
+    }
+}
+
+/// waits until `deadline`, which only passes when a [`VirtualClock`] is advanced if one
+/// was installed when this was called
+pub async fn sleep_until(deadline: Instant) {
+    let Some(state) = installed() else {
+        return tokio::time::sleep_until(deadline).await;
+    };
+
+    poll_fn(|cx| {
+        let mut state = state.borrow_mut();
+        if state.now >= deadline {
+            return Poll::Ready(());
+        }
+
+        state.sleepers.push((deadline, cx.waker().clone()));
+        Poll::Pending
+    })
+    .await
+}
+
+/// waits for `duration` from when this is called rather than first polled, see [`sleep_until`]
+pub fn sleep(duration: Duration) -> impl Future<Output = ()> {
+    sleep_until(now() + duration)
+}
+
+/// time which only moves when told to, so anything waiting with [`sleep`] can be tested
+/// without really waiting
+///
+/// the clock is installed on the current thread until it's dropped,
+/// which puts back whatever clock was installed before
+pub struct VirtualClock {
+    state: Rc<RefCell<State>>,
+    previous: Option<Rc<RefCell<State>>>,
+}
+
+impl VirtualClock {
+    pub fn install(start: Instant) -> Self {
+        let state = Rc::new(RefCell::new(State {
+            now: start,
+            sleepers: Vec::new(),
+        }));
+        let previous = VIRTUAL.with_borrow_mut(|installed| installed.replace(state.clone()));
+
+        Self { state, previous }
+    }
+
+    pub fn now(&self) -> Instant {
+        self.state.borrow().now
+    }
+
+    /// the earliest time anything is waiting for
+    pub fn deadline(&self) -> Option<Instant> {
+        self.state
+            .borrow()
+            .sleepers
+            .iter()
+            .map(|(deadline, _)| *deadline)
+            .min()
+    }
+
+    /// moves time forward to `until`, waking everything waiting for it,
+    /// time never goes backwards so earlier instants are ignored
+    pub fn advance_to(&self, until: Instant) {
+        let due = {
+            let mut state = self.state.borrow_mut();
+            state.now = state.now.max(until);
+
+            let now = state.now;
+            let (due, waiting) = state
+                .sleepers
+                .drain(..)
+                .partition::<Vec<_>, _>(|(deadline, _)| *deadline <= now);
+            state.sleepers = waiting;
+            due
+        };
+
+        for (_, waker) in due {
+            waker.wake();
+        }
+    }
+
+    /// moves time forward by `duration`, see [`advance_to`](Self::advance_to)
+    pub fn advance(&self, duration: Duration) {
+        self.advance_to(self.now() + duration);
+    }
+}
+
+impl Drop for VirtualClock {
+    fn drop(&mut self) {
+        VIRTUAL.set(self.previous.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_virtual() {
+        // far enough ahead that real time can't be mistaken for it
+        let start = Instant::now() + SECOND * 3600;
+        let clock = VirtualClock::install(start);
+        assert_eq!(now(), start, "installed clock should be used");
+
+        let mut short = Box::pin(sleep(SECOND));
+        let mut long = Box::pin(sleep(SECOND * 3));
+        assert!(short.as_mut().now_or_never().is_none());
+        assert_eq!(clock.deadline(), Some(start + SECOND));
+
+        clock.advance(SECOND);
+        assert!(
+            short.now_or_never().is_some(),
+            "sleep should finish once its deadline passes"
+        );
+        assert!(long.as_mut().now_or_never().is_none());
+        assert_eq!(clock.deadline(), Some(start + SECOND * 3));
+
+        clock.advance_to(start);
+        assert_eq!(clock.now(), start + SECOND, "time should not go backwards");
+
+        drop(clock);
+        assert!(now() < start, "real time should be used once uninstalled");
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use super::queue::QueueMetrics;
+
+/// timing information for the frame about to be drawn
+#[derive(Debug, Clone, Copy)]
+pub struct FrameTiming {
+    /// number of frames drawn before this one
+    pub frame: usize,
+    /// time since the previous frame was drawn
+    pub delta: Duration,
+    /// time since the first frame was drawn
+    pub elapsed: Duration,
+    /// state of the message queue when the frame was drawn
+    pub queue: QueueMetrics,
+}
+
+pub(crate) struct FrameClock {
+    frame: usize,
+    start: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self {
+            frame: 0,
+            start: None,
+            last: None,
+        }
+    }
+
+    pub fn next(&mut self, now: Instant) -> FrameTiming {
+        let start = *self.start.get_or_insert(now);
+        let last = self.last.replace(now).unwrap_or(now);
+
+        let timing = FrameTiming {
+            frame: self.frame,
+            delta: now - last,
+            elapsed: now - start,
+            queue: QueueMetrics::default(),
+        };
+        self.frame = self.frame.wrapping_add(1);
+
+        timing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing() {
+        let start = Instant::now();
+        let mut clock = FrameClock::new();
+
+        let first = clock.next(start);
+        assert_eq!(first.frame, 0);
+        assert_eq!(
+            first.delta,
+            Duration::ZERO,
+            "first frame should have no delta"
+        );
+
+        clock.next(start + Duration::from_millis(10));
+        let third = clock.next(start + Duration::from_millis(25));
+        assert_eq!(third.frame, 2);
+        assert_eq!(third.delta, Duration::from_millis(15));
+        assert_eq!(third.elapsed, Duration::from_millis(25));
+    }
+}
@@ -0,0 +1,328 @@
+use std::{collections::VecDeque, fmt::Debug, time::Duration};
+
+use crossterm::event::Event;
+use eyre::Result;
+use futures::{
+    future::LocalBoxFuture,
+    stream::{FuturesUnordered, LocalBoxStream, SelectAll},
+    FutureExt, StreamExt,
+};
+use log::trace;
+use ratatui::{backend::TestBackend, buffer::Buffer, layout::Size, Terminal};
+use tokio::time::Instant;
+
+use crate::{AppMessage, Model};
+
+use super::{
+    clock::VirtualClock, frame::FrameClock, subscription::Scheduler, task::Tasks, RuntimeMessage,
+};
+
+pub(crate) struct Headless<T> {
+    clock: VirtualClock,
+    queue: VecDeque<RuntimeMessage<T>>,
+    tasks: FuturesUnordered<LocalBoxFuture<'static, RuntimeMessage<T>>>,
+    scheduler: Scheduler<T>,
+    keyed: Tasks,
+    streams: SelectAll<LocalBoxStream<'static, RuntimeMessage<T>>>,
+}
+
+impl<T: Debug + 'static> Headless<T> {
+    pub fn new(size: Size, now: Instant) -> Self {
+        Self {
+            clock: VirtualClock::install(now),
+            queue: VecDeque::from([
+                RuntimeMessage::App(AppMessage::Init),
+                RuntimeMessage::App(AppMessage::Event(Event::Resize(size.width, size.height))),
+            ]),
+            tasks: FuturesUnordered::new(),
+            scheduler: Scheduler::default(),
+            keyed: Tasks::default(),
+            streams: SelectAll::new(),
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// the earliest time a subscription or sleep is waiting for
+    pub fn deadline(&self) -> Option<Instant> {
+        [self.scheduler.deadline(), self.clock.deadline()]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// moves time forward to `until`, settling after every subscription and sleep due on the way
+    /// so timers fire exactly as often as they would have in real time
+    pub fn advance(&mut self, model: &mut impl Model<T>, until: Instant) -> bool {
+        while let Some(deadline) = self.deadline().filter(|d| *d <= until) {
+            self.clock.advance_to(deadline);
+            if !self.settle(model) {
+                return false;
+            }
+        }
+        self.clock.advance_to(until);
+
+        self.settle(model)
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.queue
+            .push_back(RuntimeMessage::App(AppMessage::Event(event)));
+    }
+
+    /// processes messages until the queue is empty and no task is ready,
+    /// returning false if the model requested an exit
+    pub fn settle(&mut self, model: &mut impl Model<T>) -> bool {
+        loop {
+            while let Some(msg) = self.queue.pop_front() {
+                match msg {
+                    RuntimeMessage::Exit => return false,
+                    RuntimeMessage::Empty => (),
+                    RuntimeMessage::Batch(msgs) => self.queue.extend(msgs),
+                    RuntimeMessage::App(msg) => {
+                        trace!("headless application msg: {:?}", msg);
+                        self.queue.push_back(model.update(msg));
+                    }
+                    RuntimeMessage::Task(task) => self.tasks.push(self.keyed.prepare(None, task)),
+                    RuntimeMessage::Keyed(id, task) => {
+                        self.tasks.push(self.keyed.prepare(Some(id), task))
+                    }
+                    RuntimeMessage::Cancel(id) => self.keyed.cancel(id),
+                    RuntimeMessage::Stream(stream) => {
+                        self.streams.push(self.keyed.prepare_stream(None, stream))
+                    }
+                    RuntimeMessage::KeyedStream(id, stream) => self
+                        .streams
+                        .push(self.keyed.prepare_stream(Some(id), stream)),
+                    RuntimeMessage::Subscribe(id, subscription) => {
+                        self.scheduler.subscribe(id, subscription, self.clock.now())
+                    }
+                    RuntimeMessage::Unsubscribe(id) => self.scheduler.unsubscribe(id),
+                    RuntimeMessage::Rerate(id, period) => {
+                        self.scheduler.rerate(id, period, self.clock.now())
+                    }
+                    // every settled event is drawn anyway
+                    RuntimeMessage::Redraw => (),
+                    // there is no terminal to give back
+                    RuntimeMessage::Suspend => (),
+                }
+            }
+
+            let due = self.scheduler.fire(self.clock.now());
+            if !due.is_empty() {
+                self.queue.extend(
+                    due.into_iter()
+                        .map(|msg| RuntimeMessage::App(AppMessage::App(msg))),
+                );
+                continue;
+            }
+
+            // tasks and streams still pending after a single poll are left behind
+            if let Some(Some(msg)) = self.tasks.next().now_or_never() {
+                self.queue.push_back(msg);
+            } else if let Some(Some(msg)) = self.streams.next().now_or_never() {
+                self.queue.push_back(msg);
+            } else {
+                return true;
+            }
+        }
+    }
+}
+
+/// drives a model with a scripted sequence of events instead of a terminal,
+/// returning the rendered buffer after each event has been fully processed
+///
+/// scripted mouse events are always delivered,
+/// and the model stopping early means fewer buffers than events may be returned
+pub async fn headless<T: Debug + 'static>(
+    model: &mut impl Model<T>,
+    events: impl IntoIterator<Item = Event>,
+    backend: TestBackend,
+) -> Result<Vec<Buffer>> {
+    drive(
+        model,
+        events.into_iter().map(|event| (event, Instant::now())),
+        backend,
+        Instant::now(),
+    )
+}
+
+/// like [`headless`], but delivers each event once its delay has passed since the start
+///
+/// time is virtual, so subscriptions and [`clock::sleep`](super::clock::sleep)s fire
+/// exactly when they would have without really waiting
+pub async fn headless_timed<T: Debug + 'static>(
+    model: &mut impl Model<T>,
+    events: impl IntoIterator<Item = (Duration, Event)>,
+    backend: TestBackend,
+) -> Result<Vec<Buffer>> {
+    let start = Instant::now();
+    drive(
+        model,
+        events
+            .into_iter()
+            .map(|(delay, event)| (event, start + delay)),
+        backend,
+        start,
+    )
+}
+
+/// delivers each event at its instant, drawing after each one
+///
+/// resizes are applied to the backend too, so the model is drawn at the size it was told about
+pub(crate) fn drive<T: Debug + 'static>(
+    model: &mut impl Model<T>,
+    events: impl IntoIterator<Item = (Event, Instant)>,
+    backend: TestBackend,
+    start: Instant,
+) -> Result<Vec<Buffer>> {
+    let mut terminal = Terminal::new(backend)?;
+    let mut headless = Headless::new(terminal.size()?, start);
+
+    let mut frames = FrameClock::new();
+    let mut buffers = Vec::new();
+    if !headless.settle(model) {
+        return Ok(buffers);
+    }
+
+    // the initial frame is drawn so views can record layout before the first event
+    model.on_frame(&frames.next(headless.clock.now()));
+    terminal.draw(|frame| model.view(frame))?;
+
+    for (event, at) in events {
+        let mut running = headless.advance(model, at);
+        if running {
+            if let Event::Resize(width, height) = event {
+                terminal.backend_mut().resize(width, height);
+            }
+            headless.push(event);
+            running = headless.settle(model);
+        }
+
+        model.on_frame(&frames.next(headless.clock.now()));
+        terminal.draw(|frame| model.view(frame))?;
+        buffers.push(terminal.backend().buffer().clone());
+
+        if !running {
+            break;
+        }
+    }
+
+    Ok(buffers)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use futures::{executor::block_on, stream::iter};
+    use ratatui::{widgets::Paragraph, Frame};
+
+    use super::*;
+    use crate::{runtime::clock, util::app_message};
+
+    #[derive(Debug)]
+    enum Message {
+        Increment,
+    }
+
+    #[derive(Default)]
+    struct Counter(u64);
+
+    impl Model<Message> for Counter {
+        fn update(&mut self, message: AppMessage<Message>) -> RuntimeMessage<Message> {
+            match message {
+                AppMessage::Event(Event::Key(KeyEvent {
+                    code: KeyCode::Char('q'),
+                    ..
+                })) => RuntimeMessage::Exit,
+                AppMessage::Event(Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    ..
+                })) => RuntimeMessage::Stream(Box::pin(iter(
+                    (0..3).map(|_| app_message(Message::Increment)),
+                ))),
+                AppMessage::Event(Event::Key(KeyEvent {
+                    code: KeyCode::Char('w'),
+                    ..
+                })) => RuntimeMessage::Task(Box::pin(async {
+                    clock::sleep(Duration::from_secs(2)).await;
+                    app_message(Message::Increment)
+                })),
+                AppMessage::Event(Event::Key(_)) => RuntimeMessage::Batch(vec![
+                    app_message(Message::Increment),
+                    RuntimeMessage::Task(Box::pin(async { app_message(Message::Increment) })),
+                ]),
+                AppMessage::App(Message::Increment) => {
+                    self.0 += 1;
+                    RuntimeMessage::Empty
+                }
+                _ => RuntimeMessage::Empty,
+            }
+        }
+
+        fn view(&mut self, frame: &mut Frame) {
+            frame.render_widget(Paragraph::new(self.0.to_string()), frame.area());
+        }
+    }
+
+    fn key(c: char) -> Event {
+        Event::Key(KeyEvent::from(KeyCode::Char(c)))
+    }
+
+    #[test]
+    fn test_headless() {
+        let mut model = Counter::default();
+        let buffers = block_on(headless(
+            &mut model,
+            [key('a'), key('b'), key('q'), key('c')],
+            TestBackend::new(3, 1),
+        ))
+        .expect("headless run should succeed");
+
+        assert_eq!(
+            buffers.len(),
+            3,
+            "events after exit should not be processed"
+        );
+        assert_eq!(buffers[0], Buffer::with_lines(["2  "]));
+        assert_eq!(buffers[1], Buffer::with_lines(["4  "]));
+        assert_eq!(model.0, 4, "model should be left in its final state");
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut model = Counter::default();
+        let buffers = block_on(headless(&mut model, [key('s')], TestBackend::new(3, 1)))
+            .expect("headless run should succeed");
+
+        assert_eq!(
+            buffers[0],
+            Buffer::with_lines(["3  "]),
+            "every streamed message should be delivered"
+        );
+    }
+
+    #[test]
+    fn test_sleep() {
+        let mut model = Counter::default();
+        let buffers = block_on(headless_timed(
+            &mut model,
+            [
+                (Duration::ZERO, key('w')),
+                (Duration::from_secs(1), Event::FocusGained),
+                (Duration::from_secs(2), Event::FocusGained),
+            ],
+            TestBackend::new(3, 1),
+        ))
+        .expect("headless run should succeed");
+
+        assert_eq!(buffers[1], Buffer::with_lines(["0  "]));
+        assert_eq!(
+            buffers[2],
+            Buffer::with_lines(["1  "]),
+            "task should wake once virtual time passes its sleep"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    future::poll_fn,
+    rc::Rc,
+    task::{Poll, Waker},
+};
+
+use super::RuntimeMessage;
+
+/// how full the message queue is and how often it overflowed, for debug overlays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueMetrics {
+    /// messages waiting to be handled
+    pub depth: usize,
+    /// most messages that have waited at once
+    pub peak: usize,
+    /// messages which arrived with the queue full, either from tasks and streams which had to
+    /// wait for room, or from the model itself which were queued past the capacity
+    pub delayed: u64,
+}
+
+struct Inner<T> {
+    messages: VecDeque<RuntimeMessage<T>>,
+    capacity: usize,
+    metrics: QueueMetrics,
+    receiver: Option<Waker>,
+    senders: Vec<Waker>,
+}
+
+impl<T> Inner<T> {
+    fn push(&mut self, message: RuntimeMessage<T>) {
+        self.messages.push_back(message);
+        self.metrics.depth = self.messages.len();
+        self.metrics.peak = self.metrics.peak.max(self.metrics.depth);
+
+        if let Some(receiver) = self.receiver.take() {
+            receiver.wake();
+        }
+    }
+}
+
+/// a bounded queue of messages for the model, shared between the event loop and the tasks it runs
+///
+/// input events never go through the queue, so a model flooding it can't starve them
+pub(crate) struct Queue<T> {
+    inner: Rc<RefCell<Inner<T>>>,
+}
+
+impl<T> Clone for Queue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Queue<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(Inner {
+                messages: VecDeque::new(),
+                capacity: capacity.max(1),
+                metrics: QueueMetrics::default(),
+                receiver: None,
+                senders: Vec::new(),
+            })),
+        }
+    }
+
+    /// queues a message from the event loop itself, such as a model reply, batch child or
+    /// subscription tick, which it can't wait for room for as it's the one making it
+    ///
+    /// these were asked for by the model, so they're queued past the capacity rather than lost,
+    /// while tasks and streams keep waiting until the queue drains back under it
+    pub fn push(&self, message: RuntimeMessage<T>) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.messages.len() >= inner.capacity {
+            inner.metrics.delayed += 1;
+        }
+
+        inner.push(message);
+    }
+
+    /// queues a message, waiting for room if the queue is full
+    pub async fn send(&self, message: RuntimeMessage<T>) {
+        let mut message = Some(message);
+        let mut waited = false;
+
+        poll_fn(|cx| {
+            let mut inner = self.inner.borrow_mut();
+            if inner.messages.len() < inner.capacity {
+                inner.push(message.take().expect("message is only sent once"));
+                return Poll::Ready(());
+            }
+
+            if !waited {
+                inner.metrics.delayed += 1;
+                waited = true;
+            }
+            // a sender polled again while waiting would otherwise be woken twice for one message
+            if !inner
+                .senders
+                .iter()
+                .any(|sender| sender.will_wake(cx.waker()))
+            {
+                inner.senders.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// the oldest message, waiting for one if the queue is empty
+    pub async fn recv(&self) -> RuntimeMessage<T> {
+        // a queue that never empties would otherwise keep the loop from yielding,
+        // leaving input and timers which need the runtime to be driven waiting forever
+        tokio::task::consume_budget().await;
+
+        poll_fn(|cx| {
+            let mut inner = self.inner.borrow_mut();
+            let Some(message) = inner.messages.pop_front() else {
+                inner.receiver = Some(cx.waker().clone());
+                return Poll::Pending;
+            };
+
+            inner.metrics.depth = inner.messages.len();
+            // senders which were dropped while waiting still have wakers here,
+            // so every sender is woken and those which don't fit wait again
+            for sender in inner.senders.drain(..) {
+                sender.wake();
+            }
+            Poll::Ready(message)
+        })
+        .await
+    }
+
+    pub fn metrics(&self) -> QueueMetrics {
+        self.inner.borrow().metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        task::Context,
+    };
+
+    use futures::{
+        executor::block_on,
+        task::{waker, ArcWake},
+        FutureExt,
+    };
+
+    use super::*;
+
+    fn message(n: usize) -> RuntimeMessage<usize> {
+        RuntimeMessage::App(crate::AppMessage::App(n))
+    }
+
+    fn unwrap(message: RuntimeMessage<usize>) -> usize {
+        match message {
+            RuntimeMessage::App(crate::AppMessage::App(n)) => n,
+            _ => panic!("message should be from the app"),
+        }
+    }
+
+    #[test]
+    fn test_bounded() {
+        let queue = Queue::new(2);
+        queue.push(message(0));
+        queue.push(message(1));
+        queue.push(message(2));
+        assert_eq!(
+            queue.metrics(),
+            QueueMetrics {
+                depth: 3,
+                peak: 3,
+                delayed: 1
+            },
+            "pushing to a full queue should go past the capacity rather than drop"
+        );
+
+        let mut send = Box::pin(queue.send(message(3)));
+        assert!(
+            send.as_mut().now_or_never().is_none(),
+            "sending to a full queue should wait"
+        );
+        assert_eq!(queue.metrics().delayed, 2);
+
+        assert_eq!(unwrap(block_on(queue.recv())), 0);
+        assert!(
+            send.as_mut().now_or_never().is_none(),
+            "senders should wait until the queue is back under its capacity"
+        );
+        assert_eq!(unwrap(block_on(queue.recv())), 1);
+        assert!(send.now_or_never().is_some(), "receiving should make room");
+        assert_eq!(unwrap(block_on(queue.recv())), 2);
+        assert_eq!(unwrap(block_on(queue.recv())), 3);
+        assert_eq!(queue.metrics().depth, 0);
+    }
+
+    #[test]
+    fn test_stale_sender() {
+        struct Flag(AtomicBool);
+
+        impl ArcWake for Flag {
+            fn wake_by_ref(flag: &Arc<Self>) {
+                flag.0.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let (stale, waiting) = (
+            Arc::new(Flag(AtomicBool::new(false))),
+            Arc::new(Flag(AtomicBool::new(false))),
+        );
+        let poll = |send: &mut Pin<Box<dyn Future<Output = ()> + '_>>, flag: &Arc<Flag>| {
+            send.as_mut()
+                .poll(&mut Context::from_waker(&waker(flag.clone())))
+                .is_pending()
+        };
+
+        let queue = Queue::new(1);
+        queue.push(message(0));
+
+        let mut first: Pin<Box<dyn Future<Output = ()>>> = Box::pin(queue.send(message(1)));
+        let mut second: Pin<Box<dyn Future<Output = ()>>> = Box::pin(queue.send(message(2)));
+        assert!(poll(&mut first, &stale));
+        assert!(poll(&mut second, &waiting));
+        assert!(poll(&mut second, &waiting));
+        assert_eq!(
+            queue.inner.borrow().senders.len(),
+            2,
+            "senders should only wait once"
+        );
+        drop(first);
+
+        assert_eq!(unwrap(block_on(queue.recv())), 0);
+        assert!(
+            waiting.0.load(Ordering::Relaxed),
+            "a dropped sender shouldn't take the wake up"
+        );
+        assert!(!poll(&mut second, &waiting));
+        assert_eq!(unwrap(block_on(queue.recv())), 2);
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    cell::Cell,
+    fmt::Debug,
+    io::{self, Write},
+    mem,
+    net::SocketAddr,
+    rc::Rc,
+};
+
+use crossterm::{cursor, event, queue, terminal};
+use eyre::Result;
+use futures::{
+    channel::mpsc::{self, Sender},
+    stream, StreamExt,
+};
+use input::Telnet;
+use log::{error, info};
+use ratatui::{
+    backend::{Backend, ClearType, CrosstermBackend, WindowSize},
+    buffer::Cell as BufferCell,
+    layout::{Position, Size},
+    Terminal,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::{spawn_local, LocalSet},
+};
+
+use crate::Model;
+
+use super::{run, Options};
+
+pub mod input;
+
+/// size terminals are assumed to be until they report their own
+const DEFAULT_SIZE: Size = Size::new(80, 24);
+
+/// flushes which can wait to be written to a client, past which it's disconnected
+/// rather than buffered forever
+const MAX_PENDING: usize = 64;
+
+/// a crossterm backend for a terminal on the other end of a connection,
+/// whose size is reported by the client rather than read from the local tty
+pub struct RemoteBackend<W: Write> {
+    backend: CrosstermBackend<W>,
+    size: Rc<Cell<Size>>,
+    cursor: Position,
+}
+
+impl<W: Write> RemoteBackend<W> {
+    /// `size` should be updated whenever the client reports a resize
+    pub fn new(writer: W, size: Rc<Cell<Size>>) -> Self {
+        Self {
+            backend: CrosstermBackend::new(writer),
+            size,
+            cursor: Position::ORIGIN,
+        }
+    }
+}
+
+impl<W: Write> Backend for RemoteBackend<W> {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a BufferCell)>,
+    {
+        self.backend.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.backend.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.backend.show_cursor()
+    }
+
+    /// the last position set, as asking the client would mean waiting on its input
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        Ok(self.cursor)
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.cursor = position.into();
+        self.backend.set_cursor_position(self.cursor)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.backend.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.backend.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        Ok(self.size.get())
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        Ok(WindowSize {
+            columns_rows: self.size.get(),
+            pixels: Size::default(),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.backend)
+    }
+}
+
+/// buffers output until flushed, then hands it to the task writing to the connection
+///
+/// flushing fails once the client falls [`MAX_PENDING`] flushes behind, as frames are drawn
+/// as changes to the previous one and so can't be skipped
+#[derive(Clone)]
+pub struct Outgoing {
+    buf: Vec<u8>,
+    tx: Sender<Vec<u8>>,
+}
+
+impl Write for Outgoing {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        self.tx.try_send(mem::take(&mut self.buf)).map_err(|err| {
+            if err.is_full() {
+                io::Error::other("client isn't keeping up with its output")
+            } else {
+                io::Error::from(io::ErrorKind::BrokenPipe)
+            }
+        })
+    }
+}
+
+/// a connected telnet client, ready to be passed to a model
+pub struct Client {
+    pub terminal: Terminal<RemoteBackend<Outgoing>>,
+    pub events: stream::LocalBoxStream<'static, io::Result<event::Event>>,
+    /// writes to the client outside of the terminal, such as to restore it
+    pub outgoing: Outgoing,
+}
+
+impl Client {
+    /// negotiates with a telnet client and sets its terminal up like [`init`](crate::init)
+    ///
+    /// must be called within a [`LocalSet`], as writing to the connection is done by a local task
+    pub fn connect(stream: TcpStream) -> Result<Self> {
+        let (reader, mut writer) = stream.into_split();
+
+        let (tx, mut rx) = mpsc::channel::<Vec<u8>>(MAX_PENDING);
+        spawn_local(async move {
+            while let Some(bytes) = rx.next().await {
+                if writer.write_all(&bytes).await.is_err() {
+                    break;
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+
+        let mut outgoing = Outgoing {
+            buf: Telnet::NEGOTIATE.to_vec(),
+            tx,
+        };
+        queue!(
+            outgoing,
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
+            event::EnableBracketedPaste,
+            event::EnableFocusChange,
+            cursor::Hide
+        )?;
+        outgoing.flush()?;
+
+        let size = Rc::new(Cell::new(DEFAULT_SIZE));
+        let terminal = Terminal::new(RemoteBackend::new(outgoing.clone(), size.clone()))?;
+
+        let events = stream::unfold(
+            (reader, vec![0; 1024], Telnet::default()),
+            move |(mut reader, mut buf, mut telnet)| {
+                let size = size.clone();
+                async move {
+                    let events = match reader.read(&mut buf).await {
+                        Ok(0) => return None,
+                        Ok(n) => telnet.feed(&buf[..n]).into_iter().map(Ok).collect(),
+                        Err(err) => vec![Err(err)],
+                    };
+                    for event in &events {
+                        if let Ok(event::Event::Resize(columns, rows)) = event {
+                            size.set(Size::new(*columns, *rows));
+                        }
+                    }
+
+                    Some((stream::iter(events), (reader, buf, telnet)))
+                }
+            },
+        )
+        .flatten()
+        .boxed_local();
+
+        Ok(Self {
+            terminal,
+            events,
+            outgoing,
+        })
+    }
+
+    /// runs a model for this client until either exits, then restores its terminal
+    pub async fn run<T: Debug + 'static>(
+        self,
+        model: impl Model<T>,
+        options: Options,
+    ) -> Result<()> {
+        let mut outgoing = self.outgoing;
+        // a client pressing ctrl+z mustn't stop the server
+        let result = run(model, self.terminal, self.events, options, false).await;
+
+        queue!(
+            outgoing,
+            cursor::Show,
+            event::DisableMouseCapture,
+            event::DisableBracketedPaste,
+            event::DisableFocusChange,
+            terminal::LeaveAlternateScreen
+        )?;
+        // the client may already be gone
+        let _ = outgoing.flush();
+
+        result
+    }
+}
+
+/// accepts telnet clients on `listener` forever, running a model made by `model` for each
+///
+/// every client runs on this thread, so models can share state through an `Rc`
+pub async fn serve<T: Debug + 'static, M: Model<T> + 'static>(
+    listener: TcpListener,
+    options: Options,
+    mut model: impl FnMut(SocketAddr) -> M,
+) -> Result<()> {
+    LocalSet::new()
+        .run_until(async move {
+            loop {
+                let (stream, addr) = listener.accept().await?;
+                info!("{} connected", addr);
+
+                let client = match Client::connect(stream) {
+                    Ok(client) => client,
+                    Err(err) => {
+                        error!("could not set up {}: {}", addr, err);
+                        continue;
+                    }
+                };
+                let (model, options) = (model(addr), options.clone());
+                spawn_local(async move {
+                    if let Err(err) = client.run(model, options).await {
+                        error!("{} errored: {}", addr, err);
+                    }
+                    info!("{} disconnected", addr);
+                });
+            }
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::Event;
+    use ratatui::Frame;
+
+    use super::*;
+    use crate::{runtime::RuntimeMessage, AppMessage};
+
+    /// exits on any key, showing the size it was last given
+    struct Dimensions(u16, u16);
+
+    impl Model<()> for Dimensions {
+        fn update(&mut self, message: AppMessage<()>) -> RuntimeMessage<()> {
+            match message {
+                AppMessage::Event(Event::Resize(columns, rows)) => {
+                    *self = Dimensions(columns, rows);
+                    RuntimeMessage::Redraw
+                }
+                AppMessage::Event(Event::Key(_)) => RuntimeMessage::Exit,
+                _ => RuntimeMessage::Empty,
+            }
+        }
+
+        fn view(&mut self, frame: &mut Frame) {
+            frame.render_widget(format!("{}x{}", self.0, self.1), frame.area());
+        }
+    }
+
+    #[test]
+    fn test_outgoing() {
+        let (tx, rx) = mpsc::channel(1);
+        let mut outgoing = Outgoing {
+            buf: Vec::new(),
+            tx,
+        };
+
+        let flushed = (0..10)
+            .take_while(|_| {
+                outgoing.write_all(b"frame").unwrap();
+                outgoing.flush().is_ok()
+            })
+            .count();
+        assert!(
+            flushed < 10,
+            "a client which isn't read from should fall behind"
+        );
+
+        drop(rx);
+        outgoing.write_all(b"frame").unwrap();
+        assert_eq!(
+            outgoing.flush().unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe,
+            "a client which is gone should be reported as such"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let local = LocalSet::new();
+        let server = async move {
+            local
+                .run_until(async move {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    Client::connect(stream)
+                        .unwrap()
+                        .run(Dimensions(0, 0), Options::default().fps(None))
+                        .await
+                        .unwrap();
+                })
+                .await;
+            // the connection is written to and closed by a local task
+            local.await;
+        };
+        let client = async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            // window size, then a key
+            stream
+                .write_all(&[255, 250, 31, 0, 20, 0, 5, 255, 240])
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            stream.write_all(b"q").await.unwrap();
+
+            let mut output = Vec::new();
+            stream.read_to_end(&mut output).await.unwrap();
+            output
+        };
+
+        let ((), output) = tokio::join!(server, client);
+        assert!(
+            output.starts_with(Telnet::NEGOTIATE),
+            "client should be negotiated with first"
+        );
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("20x5"), "client size should be used");
+        assert!(
+            output.ends_with("\x1b[?1049l"),
+            "terminal should be restored on exit"
+        );
+    }
+}
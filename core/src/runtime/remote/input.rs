@@ -0,0 +1,563 @@
+use std::{mem, str};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+const ESC: u8 = 0x1b;
+
+/// bracketed paste markers, the start without its csi
+const PASTE_START: &[u8] = b"200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+/// longest escape sequence which is waited for, past which its escape is dropped
+const MAX_SEQUENCE: usize = 64;
+/// longest paste which is buffered until it ends, longer ones are delivered in pieces
+const MAX_PASTE: usize = 1 << 16;
+
+/// telnet commands, see rfc 854
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const NAWS: u8 = 31;
+/// longest subnegotiation which is waited for, nothing needed is ever this long
+const MAX_SUBNEGOTIATION: usize = 64;
+
+/// result of parsing one event from the front of the input
+enum Parsed {
+    /// an event, possibly none for ignored input, and how many bytes it used
+    Event(Option<Event>, usize),
+    /// more bytes are needed
+    Incomplete,
+}
+
+/// turns the bytes a terminal sends into events, without telnet negotiation
+#[derive(Default)]
+pub struct Parser {
+    pending: Vec<u8>,
+    /// a carriage return was just parsed, so a following newline is part of it
+    carriage: bool,
+    /// a paste too long to buffer is being delivered in pieces
+    pasting: bool,
+}
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<Event> {
+    Some(Event::Key(KeyEvent::new(code, modifiers)))
+}
+
+/// modifiers from the xterm `1 + bits` parameter
+fn modifiers(param: u16) -> KeyModifiers {
+    let bits = param.saturating_sub(1);
+    let mut modifiers = KeyModifiers::NONE;
+    if bits & 1 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if bits & 2 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if bits & 4 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+    modifiers
+}
+
+fn char_key(c: char, modifiers: KeyModifiers) -> Option<Event> {
+    let modifiers = if c.is_uppercase() {
+        modifiers | KeyModifiers::SHIFT
+    } else {
+        modifiers
+    };
+    key(KeyCode::Char(c), modifiers)
+}
+
+/// sgr mouse reports, `<button;column;row` followed by M or m
+fn mouse(params: &[u8], release: bool) -> Option<Event> {
+    let mut params = str::from_utf8(params.strip_prefix(b"<")?)
+        .ok()?
+        .split(';')
+        .map(|p| p.parse::<u16>().ok());
+    let (button, column, row) = (params.next()??, params.next()??, params.next()??);
+
+    let mut modifiers = KeyModifiers::NONE;
+    if button & 4 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if button & 8 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if button & 16 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+
+    let pressed = match button & 3 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    };
+    let kind = match (button & 64 != 0, button & 32 != 0, pressed) {
+        (true, ..) => match button & 3 {
+            0 => MouseEventKind::ScrollUp,
+            1 => MouseEventKind::ScrollDown,
+            2 => MouseEventKind::ScrollLeft,
+            _ => MouseEventKind::ScrollRight,
+        },
+        (false, true, Some(pressed)) => MouseEventKind::Drag(pressed),
+        (false, true, None) => MouseEventKind::Moved,
+        (false, false, Some(pressed)) if release => MouseEventKind::Up(pressed),
+        (false, false, Some(pressed)) => MouseEventKind::Down(pressed),
+        (false, false, None) => return None,
+    };
+
+    Some(Event::Mouse(MouseEvent {
+        kind,
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+        modifiers,
+    }))
+}
+
+/// a csi sequence with its parameter bytes and final byte
+fn csi(params: &[u8], end: u8) -> Option<Event> {
+    if params.starts_with(b"<") {
+        return mouse(params, end == b'm');
+    }
+
+    let mut numbers = str::from_utf8(params)
+        .ok()?
+        .split(';')
+        .map(|p| p.parse::<u16>().unwrap_or(1));
+    let first = numbers.next().unwrap_or(1);
+    let modifiers = modifiers(numbers.next().unwrap_or(1));
+
+    let code = match end {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        b'Z' => return key(KeyCode::BackTab, KeyModifiers::SHIFT),
+        b'I' => return Some(Event::FocusGained),
+        b'O' => return Some(Event::FocusLost),
+        b'~' => match first {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            11..=15 => KeyCode::F((first - 10) as u8),
+            17..=21 => KeyCode::F((first - 11) as u8),
+            23 | 24 => KeyCode::F((first - 12) as u8),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    key(code, modifiers)
+}
+
+impl Parser {
+    /// parses as many events as `bytes` completes, keeping any partial sequence for later
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        let mut pending = mem::take(&mut self.pending);
+        pending.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        while start < pending.len() {
+            match self.parse(&pending[start..]) {
+                Parsed::Event(event, used) => {
+                    events.extend(event);
+                    start += used;
+                }
+                // a lone escape can't be told apart from the start of a sequence,
+                // but sequences arrive whole in practice so it's taken as the key
+                Parsed::Incomplete if !self.pasting && pending[start..] == [ESC] => {
+                    events.extend(key(KeyCode::Esc, KeyModifiers::NONE));
+                    start += 1;
+                }
+                Parsed::Incomplete => break,
+            }
+        }
+        pending.drain(..start);
+        self.pending = pending;
+
+        events
+    }
+
+    fn parse(&mut self, bytes: &[u8]) -> Parsed {
+        if self.pasting {
+            return self.paste(bytes, 0);
+        }
+        let carriage = mem::take(&mut self.carriage);
+
+        let event = match bytes[0] {
+            b'\n' if carriage => None,
+            b'\r' => {
+                self.carriage = true;
+                key(KeyCode::Enter, KeyModifiers::NONE)
+            }
+            b'\n' => key(KeyCode::Enter, KeyModifiers::NONE),
+            b'\t' => key(KeyCode::Tab, KeyModifiers::NONE),
+            0x7f | 0x08 => key(KeyCode::Backspace, KeyModifiers::NONE),
+            0 => key(KeyCode::Char(' '), KeyModifiers::CONTROL),
+            c @ 1..=26 => key(KeyCode::Char((b'a' + c - 1) as char), KeyModifiers::CONTROL),
+            ESC => return self.escape(bytes),
+            _ => return Self::utf8(bytes, KeyModifiers::NONE, 0),
+        };
+
+        Parsed::Event(event, 1)
+    }
+
+    fn escape(&mut self, bytes: &[u8]) -> Parsed {
+        match bytes.get(1) {
+            None => Parsed::Incomplete,
+            Some(b'[') => {
+                let Some(end) = bytes[2..].iter().position(|b| (0x40..=0x7e).contains(b)) else {
+                    if bytes.len() > MAX_SEQUENCE {
+                        return Parsed::Event(None, 1);
+                    }
+                    return Parsed::Incomplete;
+                };
+                let end = end + 2;
+                if bytes[2..=end] == *PASTE_START {
+                    return self.paste(&bytes[end + 1..], end + 1);
+                }
+                Parsed::Event(csi(&bytes[2..end], bytes[end]), end + 1)
+            }
+            Some(b'O') => match bytes.get(2) {
+                None => Parsed::Incomplete,
+                Some(end) => Parsed::Event(csi(&[], *end), 3),
+            },
+            Some(&ESC) => Parsed::Event(key(KeyCode::Esc, KeyModifiers::NONE), 1),
+            Some(_) => Self::utf8(bytes, KeyModifiers::ALT, 1),
+        }
+    }
+
+    /// pasted text up to the end of the paste, which starts `offset` bytes in
+    fn paste(&mut self, bytes: &[u8], offset: usize) -> Parsed {
+        let text = |bytes| String::from_utf8_lossy(bytes).replace("\r\n", "\n");
+
+        match bytes.windows(PASTE_END.len()).position(|w| w == PASTE_END) {
+            Some(len) => {
+                self.pasting = false;
+                let paste = Event::Paste(text(&bytes[..len]));
+                Parsed::Event(Some(paste), offset + len + PASTE_END.len())
+            }
+            // keeping back what could be the start of the end, so it's still found
+            None if bytes.len() > MAX_PASTE => {
+                self.pasting = true;
+                let len = bytes.len() - (PASTE_END.len() - 1);
+                Parsed::Event(Some(Event::Paste(text(&bytes[..len]))), offset + len)
+            }
+            None => Parsed::Incomplete,
+        }
+    }
+
+    /// a character starting at `offset`, possibly split across reads
+    fn utf8(bytes: &[u8], modifiers: KeyModifiers, offset: usize) -> Parsed {
+        let rest = &bytes[offset..];
+        let len = match rest[0] {
+            0x00..0x80 => 1,
+            0xc0..0xe0 => 2,
+            0xe0..0xf0 => 3,
+            0xf0..0xf8 => 4,
+            _ => return Parsed::Event(None, offset + 1),
+        };
+        if rest.len() < len {
+            return Parsed::Incomplete;
+        }
+
+        let event = str::from_utf8(&rest[..len])
+            .ok()
+            .and_then(|s| s.chars().next())
+            .and_then(|c| char_key(c, modifiers));
+        Parsed::Event(event, offset + len)
+    }
+}
+
+/// where a subnegotiation's parameters end, and the parameters with doubled IACs made single
+fn subnegotiation(bytes: &[u8]) -> Option<(usize, Vec<u8>)> {
+    let mut params = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (IAC, None) => return None,
+            (IAC, Some(&SE)) => return Some((i, params)),
+            (IAC, Some(&IAC)) => {
+                params.push(IAC);
+                i += 2;
+            }
+            (byte, _) => {
+                params.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+/// strips telnet negotiation from client input, reporting window size changes as resizes
+#[derive(Default)]
+pub struct Telnet {
+    parser: Parser,
+    /// a command split across reads
+    pending: Vec<u8>,
+    /// the last byte of input was a carriage return, so a null following it, even in the
+    /// next read, is part of it
+    carriage_return: bool,
+}
+
+impl Telnet {
+    /// options a server sends so the client forwards each key and its window size,
+    /// leaving echoing to the server
+    pub const NEGOTIATE: &[u8] = &[
+        // will echo
+        IAC, 251, 1, //
+        // will suppress go ahead
+        IAC, 251, 3, //
+        // do negotiate about window size
+        IAC, 253, NAWS,
+    ];
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.pending.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        let mut data = Vec::new();
+        let mut i = 0;
+        while i < self.pending.len() {
+            let rest = &self.pending[i..];
+            if rest[0] != IAC {
+                // clients send a carriage return as \r\0
+                if rest[0] != 0 || !self.carriage_return {
+                    data.push(rest[0]);
+                }
+                self.carriage_return = rest[0] == b'\r';
+                i += 1;
+                continue;
+            }
+
+            let used = match rest.get(1) {
+                None => break,
+                Some(&IAC) => 2,
+                Some(251..=254) if rest.len() < 3 => break,
+                Some(251..=254) => 3,
+                Some(&SB) => match subnegotiation(&rest[2..]) {
+                    Some((end, params)) => {
+                        if let [NAWS, w1, w2, h1, h2] = params[..] {
+                            events.extend(self.parser.feed(&data));
+                            data.clear();
+                            events.push(Event::Resize(
+                                u16::from_be_bytes([w1, w2]),
+                                u16::from_be_bytes([h1, h2]),
+                            ));
+                        }
+                        end + 4
+                    }
+                    // never ended, so it's dropped rather than waited for forever
+                    None if rest.len() > MAX_SUBNEGOTIATION => 2,
+                    None => break,
+                },
+                Some(_) => 2,
+            };
+            i += used;
+        }
+        self.pending.drain(..i);
+
+        events.extend(self.parser.feed(&data));
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(events: Vec<Event>) -> Vec<KeyEvent> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::Key(key) => Some(key),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            keys(parser.feed(b"aB\r\n\x03\x1b[A\x1b[1;5C\x1b[3~\x1bx\x7f")),
+            [
+                KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('B'), KeyModifiers::SHIFT),
+                KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                KeyEvent::new(KeyCode::Up, KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL),
+                KeyEvent::new(KeyCode::Delete, KeyModifiers::NONE),
+                KeyEvent::new(KeyCode::Char('x'), KeyModifiers::ALT),
+                KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE),
+            ]
+        );
+
+        assert_eq!(
+            keys(parser.feed(b"\x1b[1;")),
+            [],
+            "partial sequences should wait"
+        );
+        assert_eq!(
+            keys(parser.feed(b"2D\xc3")),
+            [KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT)]
+        );
+        assert_eq!(
+            keys(parser.feed(b"\xa9")),
+            [KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE)],
+            "characters split across reads should be joined"
+        );
+        assert_eq!(
+            keys(parser.feed(b"\x1b")),
+            [KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)]
+        );
+    }
+
+    #[test]
+    fn test_mouse() {
+        let mut parser = Parser::default();
+        assert_eq!(
+            parser.feed(b"\x1b[<0;5;3M\x1b[<0;5;3m\x1b[<65;1;1M"),
+            [
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column: 4,
+                    row: 2,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Up(MouseButton::Left),
+                    column: 4,
+                    row: 2,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::ScrollDown,
+                    column: 0,
+                    row: 0,
+                    modifiers: KeyModifiers::NONE,
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paste() {
+        let mut parser = Parser::default();
+        assert_eq!(parser.feed(b"\x1b[200~a\x1bb"), [], "pastes should be whole");
+        assert_eq!(
+            parser.feed(b"\r\nc\x1b[201~d"),
+            [
+                Event::Paste("a\x1bb\nc".to_string()),
+                Event::Key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_long_paste() {
+        let mut parser = Parser::default();
+        let mut bytes = b"\x1b[200~".to_vec();
+        bytes.resize(bytes.len() + MAX_PASTE + 1, b'a');
+
+        let events = parser.feed(&bytes);
+        assert!(
+            matches!(&events[..], [Event::Paste(text)] if text.len() == MAX_PASTE - 4),
+            "long pastes should be delivered before they end"
+        );
+        assert!(parser.pending.len() < PASTE_END.len());
+
+        assert_eq!(
+            parser.feed(b"b\x1b[201~c"),
+            [
+                Event::Paste("aaaaab".to_string()),
+                Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE))
+            ],
+            "the rest should still be pasted"
+        );
+    }
+
+    #[test]
+    fn test_unterminated() {
+        let mut parser = Parser::default();
+        let mut bytes = b"\x1b[".to_vec();
+        bytes.resize(MAX_SEQUENCE + 1, b'1');
+        assert_eq!(
+            keys(parser.feed(&bytes)).len(),
+            MAX_SEQUENCE,
+            "sequences which never end should be dropped"
+        );
+        assert!(parser.pending.is_empty());
+    }
+
+    #[test]
+    fn test_telnet() {
+        let mut telnet = Telnet::default();
+        assert_eq!(
+            telnet.feed(&[IAC, 251, NAWS, b'a', IAC, SB, NAWS, 0, 100, 0]),
+            [Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::NONE
+            ))],
+            "negotiation should be dropped"
+        );
+        assert_eq!(
+            telnet.feed(&[40, IAC, SE, b'\r', 0]),
+            [
+                Event::Resize(100, 40),
+                Event::Key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE))
+            ],
+            "window size should be reported once complete"
+        );
+
+        assert_eq!(
+            keys(telnet.feed(b"\r")),
+            [KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)]
+        );
+        assert_eq!(
+            telnet.feed(&[0]),
+            [],
+            "a carriage return's null should be dropped even if it's read separately"
+        );
+        assert_eq!(
+            keys(telnet.feed(&[0])),
+            [KeyEvent::new(KeyCode::Char(' '), KeyModifiers::CONTROL)],
+            "only the null right after a carriage return should be dropped"
+        );
+    }
+
+    #[test]
+    fn test_subnegotiation() {
+        let mut telnet = Telnet::default();
+        assert_eq!(
+            telnet.feed(&[IAC, SB, NAWS, 0, IAC, IAC, 0, SE, IAC, SE]),
+            [Event::Resize(255, 240)],
+            "doubled IACs should be single"
+        );
+
+        let mut bytes = vec![IAC, SB];
+        bytes.resize(MAX_SUBNEGOTIATION + 1, b'a');
+        let events = telnet.feed(&bytes);
+        assert_eq!(
+            keys(events).len(),
+            MAX_SUBNEGOTIATION - 1,
+            "unterminated subnegotiations should be dropped"
+        );
+        assert!(telnet.pending.is_empty());
+    }
+}
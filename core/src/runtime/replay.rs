@@ -0,0 +1,363 @@
+use std::{
+    fmt::Debug,
+    fs::File,
+    future::Future,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    pin::pin,
+    time::Duration,
+};
+
+use crossterm::event::Event;
+use eyre::{eyre, Result};
+use futures::{stream, Stream, StreamExt};
+use ratatui::{
+    backend::{Backend, TestBackend},
+    buffer::Buffer,
+    layout::Size,
+    Terminal,
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::{sleep_until, Instant};
+
+use crate::Model;
+
+use super::{
+    clock,
+    frame::FrameClock,
+    headless::{drive, Headless},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Header {
+    width: u16,
+    height: u16,
+}
+
+/// an event along with when it arrived, relative to the start of the session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timed {
+    pub at: Duration,
+    pub event: Event,
+}
+
+/// writes every incoming event to a file as json lines, after a header holding the terminal size
+pub struct Recorder<W: Write> {
+    writer: W,
+    start: Instant,
+}
+
+impl Recorder<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>, size: (u16, u16)) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), size)
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(mut writer: W, (width, height): (u16, u16)) -> Result<Self> {
+        serde_json::to_writer(&mut writer, &Header { width, height })?;
+        writeln!(writer)?;
+
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, event: &Event) -> Result<()> {
+        let timed = Timed {
+            at: self.start.elapsed(),
+            event: event.clone(),
+        };
+        serde_json::to_writer(&mut self.writer, &timed)?;
+        writeln!(self.writer)?;
+
+        // flushed every event so a crash doesn't lose the end of the session
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// a recorded session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub size: (u16, u16),
+    pub events: Vec<Timed>,
+}
+
+impl Recording {
+    pub fn read(reader: impl BufRead) -> Result<Self> {
+        let mut lines = reader.lines();
+        let header: Header =
+            serde_json::from_str(&lines.next().ok_or(eyre!("recording is empty"))??)?;
+
+        let events = lines
+            .filter(|line| !line.as_ref().is_ok_and(|line| line.is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            size: (header.width, header.height),
+            events,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+}
+
+/// replays a recording into a model without a terminal, returning the buffer after each event
+///
+/// time is virtual, so subscriptions fire exactly when they would have during the session
+/// no matter how long the replay takes
+pub fn replay<T: Debug + 'static>(
+    model: &mut impl Model<T>,
+    recording: &Recording,
+) -> Result<Vec<Buffer>> {
+    let (width, height) = recording.size;
+    let start = Instant::now();
+
+    drive(
+        model,
+        recording
+            .events
+            .iter()
+            .map(|timed| (timed.event.clone(), start + timed.at)),
+        TestBackend::new(width, height),
+        start,
+    )
+}
+
+/// copies `screen` into the top left of `terminal`, cutting off whatever doesn't fit
+fn show<B: Backend>(terminal: &mut Terminal<B>, screen: &Buffer) -> Result<()> {
+    terminal.draw(|frame| {
+        let area = screen.area.intersection(frame.area());
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                frame.buffer_mut()[(x, y)] = screen[(x, y)].clone();
+            }
+        }
+    })?;
+
+    Ok(())
+}
+
+/// plays a recording back on `terminal` at the pace it was recorded, until it ends or `stop` resolves
+///
+/// the model runs on virtual time and is drawn at the recorded size like [`replay`],
+/// so it sees exactly what it saw during the session, whatever the size of `terminal`
+pub async fn play<T: Debug + 'static, B: Backend>(
+    model: &mut impl Model<T>,
+    terminal: &mut Terminal<B>,
+    recording: &Recording,
+    stop: impl Future<Output = ()>,
+) -> Result<()> {
+    let (width, height) = recording.size;
+    let mut screen = Terminal::new(TestBackend::new(width, height))?;
+    let start = Instant::now();
+    let mut headless = Headless::new(Size::new(width, height), start);
+    let mut frames = FrameClock::new();
+    let mut stop = pin!(stop);
+
+    let mut events = recording.events.iter().peekable();
+    let mut running = headless.settle(model);
+    while running {
+        model.on_frame(&frames.next(headless.now()));
+        screen.draw(|frame| model.view(frame))?;
+        show(terminal, screen.backend().buffer())?;
+
+        let Some(timed) = events.peek() else {
+            break;
+        };
+
+        // virtual time started with real time, so whatever is due next is waited for in real time
+        let at = start + timed.at;
+        let next = headless.deadline().filter(|d| *d < at).unwrap_or(at);
+        tokio::select! {
+            _ = sleep_until(next) => (),
+            _ = &mut stop => break,
+        }
+
+        running = headless.advance(model, next);
+        if running && next == at {
+            if let Event::Resize(width, height) = timed.event {
+                screen.backend_mut().resize(width, height);
+            }
+            headless.push(timed.event.clone());
+            running = headless.settle(model);
+            events.next();
+        }
+    }
+
+    Ok(())
+}
+
+/// the recorded events as a source for [`event_loop_with`](super::event_loop_with),
+/// each delivered as long after this is called as it was after the session started
+///
+/// the loop exits once the last event has been delivered
+pub fn playback(recording: Recording) -> impl Stream<Item = io::Result<Event>> + 'static {
+    let start = clock::now();
+    stream::iter(recording.events).then(move |timed| async move {
+        clock::sleep_until(start + timed.at).await;
+        Ok(timed.event)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use futures::{
+        future::{pending, ready},
+        FutureExt,
+    };
+    use ratatui::{widgets::Paragraph, Frame};
+
+    use super::*;
+    use crate::{
+        runtime::{subscription::Subscription, RuntimeMessage},
+        AppMessage,
+    };
+
+    /// counts ticks of a one second interval
+    #[derive(Default)]
+    struct Ticks(u64);
+
+    impl Model<()> for Ticks {
+        fn update(&mut self, message: AppMessage<()>) -> RuntimeMessage<()> {
+            match message {
+                AppMessage::Init => RuntimeMessage::Subscribe(
+                    "tick",
+                    Subscription::interval(Duration::from_secs(1), || ()),
+                ),
+                AppMessage::App(()) => {
+                    self.0 += 1;
+                    RuntimeMessage::Empty
+                }
+                _ => RuntimeMessage::Empty,
+            }
+        }
+
+        fn view(&mut self, frame: &mut Frame) {
+            let area = frame.area();
+            frame.render_widget(
+                Paragraph::new(format!("{}x{} {}", area.width, area.height, self.0)),
+                area,
+            );
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut file = Vec::new();
+        let mut recorder = Recorder::new(&mut file, (80, 24)).expect("header should be written");
+        recorder
+            .record(&Event::Key(KeyEvent::from(KeyCode::Char('a'))))
+            .expect("event should be written");
+        recorder
+            .record(&Event::Resize(40, 12))
+            .expect("event should be written");
+
+        let recording = Recording::read(file.as_slice()).expect("recording should parse");
+        assert_eq!(recording.size, (80, 24));
+        assert_eq!(
+            recording
+                .events
+                .into_iter()
+                .map(|timed| timed.event)
+                .collect::<Vec<_>>(),
+            vec![
+                Event::Key(KeyEvent::from(KeyCode::Char('a'))),
+                Event::Resize(40, 12)
+            ],
+            "events should be read back in order"
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let recording = Recording {
+            size: (1, 1),
+            events: vec![
+                Timed {
+                    at: Duration::from_millis(2500),
+                    event: Event::FocusGained,
+                },
+                Timed {
+                    at: Duration::from_millis(4000),
+                    event: Event::FocusLost,
+                },
+            ],
+        };
+
+        let mut model = Ticks::default();
+        let buffers = replay(&mut model, &recording).expect("replay should succeed");
+        assert_eq!(buffers.len(), 2);
+        assert_eq!(
+            model.0, 4,
+            "every tick before the last event should fire, however long the gaps are"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_play() {
+        let recording = Recording {
+            size: (5, 1),
+            events: vec![Timed {
+                at: Duration::from_millis(10),
+                event: Event::Resize(6, 1),
+            }],
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(8, 2)).expect("test backends don't fail");
+        play(&mut Ticks::default(), &mut terminal, &recording, pending())
+            .await
+            .expect("play should succeed");
+        assert_eq!(
+            terminal.backend().buffer(),
+            &Buffer::with_lines(["6x1 0   ", "        "]),
+            "the model should be drawn at the recorded size, following recorded resizes"
+        );
+
+        let recording = Recording {
+            size: (5, 1),
+            events: vec![Timed {
+                at: Duration::from_secs(3600),
+                event: Event::FocusGained,
+            }],
+        };
+        play(&mut Ticks::default(), &mut terminal, &recording, ready(()))
+            .await
+            .expect("play should succeed");
+        assert_eq!(
+            terminal.backend().buffer(),
+            &Buffer::with_lines(["5x1 0   ", "        "]),
+            "stopping should leave the first frame without waiting"
+        );
+    }
+
+    #[test]
+    fn test_playback() {
+        let clock = clock::VirtualClock::install(Instant::now());
+        let recording = Recording {
+            size: (1, 1),
+            events: vec![Timed {
+                at: Duration::from_secs(1),
+                event: Event::FocusGained,
+            }],
+        };
+
+        let mut events = Box::pin(playback(recording));
+        assert!(
+            events.next().now_or_never().is_none(),
+            "events should wait until they were recorded"
+        );
+        clock.advance(Duration::from_secs(1));
+        assert!(matches!(
+            events.next().now_or_never(),
+            Some(Some(Ok(Event::FocusGained)))
+        ));
+        assert!(matches!(events.next().now_or_never(), Some(None)));
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, time::Duration};
+
+use tokio::time::Instant;
+
+pub type SubscriptionId = &'static str;
+
+/// shortest period an interval can have, as one which never advances would fire forever
+const MIN_PERIOD: Duration = Duration::from_millis(1);
+
+/// a timer owned by the runtime which delivers messages to the model
+pub struct Subscription<T> {
+    delay: Duration,
+    period: Option<Duration>,
+    message: Box<dyn FnMut() -> T>,
+}
+
+impl<T: 'static> Subscription<T> {
+    /// fires every `period`, starting one period after subscribing
+    pub fn interval(period: Duration, message: impl FnMut() -> T + 'static) -> Self {
+        Self {
+            delay: period,
+            period: Some(period),
+            message: Box::new(message),
+        }
+    }
+
+    /// fires once after `delay`, then unsubscribes itself
+    pub fn timeout(delay: Duration, message: impl FnMut() -> T + 'static) -> Self {
+        Self {
+            delay,
+            period: None,
+            message: Box::new(message),
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U + 'static + Clone) -> Subscription<U> {
+        let mut message = self.message;
+        Subscription {
+            delay: self.delay,
+            period: self.period,
+            message: Box::new(move || f.clone()(message())),
+        }
+    }
+}
+
+struct Entry<T> {
+    deadline: Instant,
+    subscription: Subscription<T>,
+}
+
+pub(crate) struct Scheduler<T> {
+    entries: HashMap<SubscriptionId, Entry<T>>,
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Scheduler<T> {
+    /// registers a subscription, replacing any existing one with the same id
+    pub fn subscribe(
+        &mut self,
+        id: SubscriptionId,
+        mut subscription: Subscription<T>,
+        now: Instant,
+    ) {
+        if let Some(period) = subscription.period.as_mut() {
+            *period = (*period).max(MIN_PERIOD);
+            subscription.delay = *period;
+        }
+
+        self.entries.insert(
+            id,
+            Entry {
+                deadline: now + subscription.delay,
+                subscription,
+            },
+        );
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriptionId) {
+        self.entries.remove(id);
+    }
+
+    /// changes the period of an interval (or the delay of a timeout), restarting it from `now`
+    pub fn rerate(&mut self, id: SubscriptionId, period: Duration, now: Instant) {
+        if let Some(entry) = self.entries.get_mut(id) {
+            let period = match entry.subscription.period.as_mut() {
+                Some(p) => {
+                    *p = period.max(MIN_PERIOD);
+                    *p
+                }
+                None => period,
+            };
+            entry.subscription.delay = period;
+            entry.deadline = now + period;
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        self.entries.values().map(|entry| entry.deadline).min()
+    }
+
+    /// collects messages from every subscription due at `now`
+    pub fn fire(&mut self, now: Instant) -> Vec<T> {
+        let mut messages = Vec::new();
+
+        self.entries.retain(|_, entry| {
+            if entry.deadline > now {
+                return true;
+            }
+
+            messages.push((entry.subscription.message)());
+            match entry.subscription.period {
+                Some(period) => {
+                    // advance from the previous deadline so intervals don't drift,
+                    // skipping any periods that were missed entirely
+                    let behind = now - entry.deadline;
+                    entry.deadline = now + period
+                        - Duration::from_nanos((behind.as_nanos() % period.as_nanos()) as u64);
+                    true
+                }
+                None => false,
+            }
+        });
+
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_interval() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::default();
+        scheduler.subscribe("tick", Subscription::interval(SECOND, || 1), start);
+
+        assert!(
+            scheduler.fire(start).is_empty(),
+            "interval should not fire before its period"
+        );
+        assert_eq!(scheduler.fire(start + SECOND), vec![1]);
+        assert_eq!(
+            scheduler.deadline(),
+            Some(start + SECOND * 2),
+            "interval should advance from its previous deadline"
+        );
+
+        // missed periods are skipped rather than fired in bulk
+        assert_eq!(scheduler.fire(start + SECOND * 5), vec![1]);
+        assert_eq!(scheduler.deadline(), Some(start + SECOND * 6));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::default();
+        scheduler.subscribe("once", Subscription::timeout(SECOND, || 1), start);
+
+        assert_eq!(scheduler.fire(start + SECOND), vec![1]);
+        assert_eq!(
+            scheduler.deadline(),
+            None,
+            "timeout should unsubscribe after firing"
+        );
+    }
+
+    #[test]
+    fn test_rerate() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::default();
+        scheduler.subscribe("tick", Subscription::interval(SECOND, || 1), start);
+        scheduler.rerate("tick", SECOND * 3, start);
+
+        assert!(scheduler.fire(start + SECOND).is_empty());
+        assert_eq!(scheduler.fire(start + SECOND * 3), vec![1]);
+
+        scheduler.unsubscribe("tick");
+        assert_eq!(scheduler.deadline(), None);
+    }
+
+    #[test]
+    fn test_zero_period() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::default();
+        scheduler.subscribe("tick", Subscription::interval(Duration::ZERO, || 1), start);
+        assert_eq!(
+            scheduler.deadline(),
+            Some(start + MIN_PERIOD),
+            "zero periods should be clamped"
+        );
+        assert_eq!(scheduler.fire(start + SECOND), vec![1]);
+        assert!(scheduler.deadline().unwrap() > start + SECOND);
+
+        scheduler.rerate("tick", Duration::ZERO, start + SECOND);
+        assert_eq!(scheduler.deadline(), Some(start + SECOND + MIN_PERIOD));
+        assert_eq!(scheduler.fire(start + SECOND * 2), vec![1]);
+    }
+}
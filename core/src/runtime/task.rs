@@ -0,0 +1,289 @@
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use futures::{
+    future::{abortable, AbortHandle, LocalBoxFuture},
+    stream::{self, LocalBoxStream},
+    FutureExt, Stream, StreamExt,
+};
+
+use crate::AppMessage;
+
+use super::RuntimeMessage;
+
+pub type TaskId = &'static str;
+
+thread_local! {
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// whether the current thread is polling a task whose panics are reported to the model
+pub fn catching() -> bool {
+    CATCHING.get()
+}
+
+/// marks the thread as catching for the duration of each poll
+struct Catching<F>(F);
+
+impl<F: Future + Unpin> Future for Catching<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let previous = CATCHING.replace(true);
+        let poll = self.0.poll_unpin(cx);
+        CATCHING.set(previous);
+
+        poll
+    }
+}
+
+impl<S: Stream + Unpin> Stream for Catching<S> {
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let previous = CATCHING.replace(true);
+        let poll = self.0.poll_next_unpin(cx);
+        CATCHING.set(previous);
+
+        poll
+    }
+}
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// running keyed tasks, along with which task it was that used the key
+type Keyed = Rc<RefCell<HashMap<TaskId, (u64, AbortHandle)>>>;
+
+/// frees a task's key once the task is dropped, such as when it finishes,
+/// unless another task has taken the key since
+struct Untrack {
+    keyed: Keyed,
+    id: TaskId,
+    generation: u64,
+}
+
+impl Drop for Untrack {
+    fn drop(&mut self) {
+        let mut keyed = self.keyed.borrow_mut();
+        if keyed
+            .get(self.id)
+            .is_some_and(|(generation, _)| *generation == self.generation)
+        {
+            keyed.remove(self.id);
+        }
+    }
+}
+
+/// tracks keyed tasks so they can be cancelled or replaced
+#[derive(Default)]
+pub(crate) struct Tasks {
+    keyed: Keyed,
+    generation: u64,
+}
+
+impl Tasks {
+    /// keeps `handle` under `id` until the task is done, aborting whatever was kept there before
+    fn track(&mut self, id: Option<TaskId>, handle: AbortHandle) -> Option<Untrack> {
+        let id = id?;
+        self.generation += 1;
+
+        let previous = self
+            .keyed
+            .borrow_mut()
+            .insert(id, (self.generation, handle));
+        if let Some((_, previous)) = previous {
+            previous.abort();
+        }
+
+        Some(Untrack {
+            keyed: self.keyed.clone(),
+            id,
+            generation: self.generation,
+        })
+    }
+
+    /// wraps a task so it can be cancelled by key, and so a panic is reported as a message
+    ///
+    /// a keyed task replaces any task already running with the same key
+    pub fn prepare<T: 'static>(
+        &mut self,
+        id: Option<TaskId>,
+        task: LocalBoxFuture<'static, RuntimeMessage<T>>,
+    ) -> LocalBoxFuture<'static, RuntimeMessage<T>> {
+        let (task, handle) = abortable(Catching(AssertUnwindSafe(task).catch_unwind()));
+        let untrack = self.track(id, handle);
+
+        Box::pin(task.map(move |result| {
+            drop(untrack);
+            match result {
+                Ok(Ok(msg)) => msg,
+                Ok(Err(panic)) => RuntimeMessage::App(AppMessage::Panic(id, panic_message(panic))),
+                Err(_aborted) => RuntimeMessage::Empty,
+            }
+        }))
+    }
+
+    /// wraps a stream like [`prepare`](Self::prepare), ending it once it's cancelled or panics
+    ///
+    /// streams and tasks share keys, so either replaces the other
+    pub fn prepare_stream<T: 'static>(
+        &mut self,
+        id: Option<TaskId>,
+        stream: LocalBoxStream<'static, RuntimeMessage<T>>,
+    ) -> LocalBoxStream<'static, RuntimeMessage<T>> {
+        let (stream, handle) = stream::abortable(Catching(AssertUnwindSafe(stream).catch_unwind()));
+        let untrack = self.track(id, handle);
+
+        Box::pin(stream.map(move |result| {
+            // held until the stream is dropped
+            let _untrack = &untrack;
+            match result {
+                Ok(msg) => msg,
+                Err(panic) => RuntimeMessage::App(AppMessage::Panic(id, panic_message(panic))),
+            }
+        }))
+    }
+
+    pub fn cancel(&mut self, id: TaskId) {
+        let cancelled = self.keyed.borrow_mut().remove(id);
+        if let Some((_, handle)) = cancelled {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use futures::{executor::block_on, future::pending};
+
+    use super::*;
+
+    #[test]
+    fn test_cancel() {
+        let mut tasks = Tasks::default();
+        let task = tasks.prepare::<()>(Some("task"), Box::pin(pending()));
+        tasks.cancel("task");
+
+        assert!(
+            matches!(block_on(task), RuntimeMessage::Empty),
+            "cancelled task should resolve empty"
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut tasks = Tasks::default();
+        let first = tasks.prepare::<u8>(Some("task"), Box::pin(pending()));
+        let second = tasks.prepare(
+            Some("task"),
+            Box::pin(async { RuntimeMessage::App(AppMessage::App(2)) }),
+        );
+
+        assert!(matches!(block_on(first), RuntimeMessage::Empty));
+        assert!(matches!(
+            block_on(second),
+            RuntimeMessage::App(AppMessage::App(2))
+        ));
+    }
+
+    #[test]
+    fn test_finished() {
+        let mut tasks = Tasks::default();
+        let first = tasks.prepare::<()>(Some("task"), Box::pin(async { RuntimeMessage::Empty }));
+        let second = tasks.prepare::<()>(Some("task"), Box::pin(async { RuntimeMessage::Empty }));
+
+        drop(first);
+        assert!(
+            tasks.keyed.borrow().contains_key("task"),
+            "a replaced task shouldn't free its replacement's key"
+        );
+
+        block_on(second);
+        assert!(
+            tasks.keyed.borrow().is_empty(),
+            "finished tasks should free their key"
+        );
+    }
+
+    #[test]
+    fn test_local() {
+        let shared = Rc::new(Cell::new(0));
+        let mut tasks = Tasks::default();
+        let task = tasks.prepare::<()>(None, {
+            let shared = shared.clone();
+            Box::pin(async move {
+                shared.set(1);
+                RuntimeMessage::Empty
+            })
+        });
+
+        block_on(task);
+        assert_eq!(shared.get(), 1, "task should be able to capture !Send state");
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut tasks = Tasks::default();
+        let mut stream = tasks.prepare_stream::<u8>(
+            Some("stream"),
+            Box::pin(stream::iter([1, 2]).map(|n| {
+                assert!(n < 2, "stream failed");
+                RuntimeMessage::App(AppMessage::App(n))
+            })),
+        );
+
+        assert!(matches!(
+            block_on(stream.next()),
+            Some(RuntimeMessage::App(AppMessage::App(1)))
+        ));
+        match block_on(stream.next()) {
+            Some(RuntimeMessage::App(AppMessage::Panic(Some("stream"), message))) => {
+                assert_eq!(message, "stream failed")
+            }
+            _ => panic!("panic should be reported as a message"),
+        }
+        assert!(
+            block_on(stream.next()).is_none(),
+            "stream should end after panicking"
+        );
+
+        let mut stream = tasks.prepare_stream::<()>(Some("stream"), Box::pin(stream::pending()));
+        tasks.cancel("stream");
+        assert!(
+            block_on(stream.next()).is_none(),
+            "cancelled stream should end"
+        );
+    }
+
+    #[test]
+    fn test_panic() {
+        let mut tasks = Tasks::default();
+        let task = tasks.prepare::<()>(Some("task"), Box::pin(async { panic!("task failed") }));
+
+        match block_on(task) {
+            RuntimeMessage::App(AppMessage::Panic(Some("task"), message)) => {
+                assert_eq!(message, "task failed")
+            }
+            _ => panic!("panic should be reported as a message"),
+        }
+        assert!(!catching(), "catching flag should be reset after polling");
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    env,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::info;
+use ratatui::{
+    backend::TestBackend,
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::{Color, Modifier},
+    widgets::Widget,
+    Frame, Terminal,
+};
+
+use crate::Model;
+
+/// set to re-record every snapshot instead of comparing against it
+pub const UPDATE_VAR: &str = "COG_UPDATE_SNAPSHOTS";
+
+/// draws into a blank buffer of a fixed size, as a terminal that size would show it
+pub fn render(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> Buffer {
+    let mut terminal =
+        Terminal::new(TestBackend::new(width, height)).expect("test backends don't fail");
+    terminal.draw(draw).expect("test backends don't fail");
+
+    terminal.backend().buffer().clone()
+}
+
+pub fn render_widget(widget: impl Widget, width: u16, height: u16) -> Buffer {
+    render(width, height, |frame| {
+        frame.render_widget(widget, frame.area())
+    })
+}
+
+/// the model's view as it is, without sending it any messages
+pub fn render_model<T: 'static>(model: &mut impl Model<T>, width: u16, height: u16) -> Buffer {
+    render(width, height, |frame| model.view(frame))
+}
+
+/// a style which differs from the terminal's default, as text
+fn describe(fg: Color, bg: Color, modifier: Modifier) -> Option<String> {
+    let mut parts = Vec::new();
+    if fg != Color::Reset {
+        parts.push(format!("fg={}", fg));
+    }
+    if bg != Color::Reset {
+        parts.push(format!("bg={}", bg));
+    }
+    if !modifier.is_empty() {
+        parts.push(format!("{:?}", modifier).to_lowercase().replace(" | ", "+"));
+    }
+
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// the buffer's text, then every run of styled cells as `row: start..end style`
+pub fn snapshot(buffer: &Buffer) -> String {
+    let Rect { width, height, .. } = buffer.area;
+    let mut text = String::new();
+    let mut styles = String::new();
+    let key = |cell: &Cell| (cell.fg, cell.bg, cell.modifier);
+
+    for y in 0..height {
+        let row = &buffer.content()[(y * width) as usize..((y + 1) * width) as usize];
+        for cell in row {
+            text.push_str(cell.symbol());
+        }
+        text.push('\n');
+
+        let mut start = 0;
+        for x in 1..=row.len() {
+            if x < row.len() && key(&row[x]) == key(&row[start]) {
+                continue;
+            }
+
+            let (fg, bg, modifier) = key(&row[start]);
+            if let Some(style) = describe(fg, bg, modifier) {
+                writeln!(styles, "{}: {}..{} {}", y, start, x, style).expect("strings don't fail");
+            }
+            start = x;
+        }
+    }
+
+    format!("{}---\n{}", text, styles)
+}
+
+/// the lines of both, marking those which were expected with `-` and those which
+/// were rendered instead with `+`
+pub fn diff(expected: &str, actual: &str) -> String {
+    let (expected, actual): (Vec<_>, Vec<_>) =
+        (expected.lines().collect(), actual.lines().collect());
+    let mut out = String::new();
+
+    for i in 0..expected.len().max(actual.len()) {
+        let (old, new) = (expected.get(i), actual.get(i));
+        if old == new {
+            writeln!(out, "  {:>3} {}", i + 1, old.unwrap_or(&"")).expect("strings don't fail");
+            continue;
+        }
+
+        if let Some(old) = old {
+            writeln!(out, "- {:>3} {}", i + 1, old).expect("strings don't fail");
+        }
+        if let Some(new) = new {
+            writeln!(out, "+ {:>3} {}", i + 1, new).expect("strings don't fail");
+        }
+    }
+
+    out
+}
+
+/// compares against the snapshot at `path`, recording it instead if `update` is set
+///
+/// a missing snapshot fails like a changed one, so one that was never committed can't pass unnoticed
+fn check(path: &Path, actual: &str, update: bool) -> Result<(), String> {
+    if update {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(path, actual).map_err(|err| err.to_string())?;
+        info!("recorded snapshot {}", path.display());
+        return Ok(());
+    }
+
+    let expected = fs::read_to_string(path).map_err(|err| {
+        format!(
+            "could not read snapshot {}, rerun with {}=1 to record it: {}",
+            path.display(),
+            UPDATE_VAR,
+            err
+        )
+    })?;
+
+    if expected == actual {
+        return Ok(());
+    }
+
+    Err(format!(
+        "snapshot {} doesn't match, rerun with {}=1 to accept the change\n{}",
+        path.display(),
+        UPDATE_VAR,
+        diff(&expected, actual)
+    ))
+}
+
+/// compares the buffer against `snapshots/<name>.snap` in the crate being tested,
+/// panicking with a diff if they differ
+///
+/// every snapshot, including missing ones, is recorded instead if [`UPDATE_VAR`] is set
+#[track_caller]
+pub fn assert_snapshot(name: &str, buffer: &Buffer) {
+    let root = env::var_os("CARGO_MANIFEST_DIR").map_or_else(|| PathBuf::from("."), PathBuf::from);
+    let path = root.join("snapshots").join(format!("{}.snap", name));
+    let update = env::var_os(UPDATE_VAR).is_some_and(|value| !value.is_empty() && value != "0");
+
+    if let Err(message) = check(&path, &snapshot(buffer), update) {
+        panic!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{style::Stylize, text::Line};
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let buffer = render_widget(Line::from(vec!["ab".red().bold(), "c".into()]), 4, 2);
+
+        assert_eq!(snapshot(&buffer), "abc \n    \n---\n0: 0..2 fg=Red bold\n");
+    }
+
+    #[test]
+    fn test_check() {
+        let path = env::temp_dir()
+            .join(format!("cog-snapshots-{}", std::process::id()))
+            .join("check.snap");
+
+        let err = check(&path, "a\nb\n", false).expect_err("missing snapshots should fail");
+        assert!(err.contains(UPDATE_VAR), "{}", err);
+        assert!(!path.exists(), "missing snapshots shouldn't be recorded");
+
+        assert!(check(&path, "a\nb\n", true).is_ok());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\n");
+
+        let err = check(&path, "a\nc\n", false).expect_err("changes should be caught");
+        assert!(err.ends_with("    1 a\n-   2 b\n+   2 c\n"), "{}", err);
+
+        assert!(check(&path, "a\nc\n", true).is_ok());
+        assert!(
+            check(&path, "a\nc\n", false).is_ok(),
+            "updating should re-record the snapshot"
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,463 @@
+use std::{cell::RefCell, collections::BTreeMap, env, sync::RwLock};
+
+use eyre::{eyre, Result};
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// colors which stay distinguishable with every common color-vision deficiency,
+/// from Okabe and Ito's "Color Universal Design"
+pub mod okabe_ito {
+    use ratatui::style::Color;
+
+    pub const BLACK: Color = Color::Rgb(0, 0, 0);
+    pub const ORANGE: Color = Color::Rgb(230, 159, 0);
+    pub const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+    pub const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+    pub const YELLOW: Color = Color::Rgb(240, 228, 66);
+    pub const BLUE: Color = Color::Rgb(0, 114, 178);
+    pub const VERMILLION: Color = Color::Rgb(213, 94, 0);
+    pub const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+}
+
+/// how many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorDepth {
+    /// no colors at all, everything is drawn in the default color
+    Mono,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// rgb values of the 16 ansi colors as xterm draws them
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// steps of each channel in the 256 color cube
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..16 => ANSI[i as usize].1,
+        16..232 => {
+            let i = i - 16;
+            (
+                CUBE[(i / 36) as usize],
+                CUBE[(i / 6 % 6) as usize],
+                CUBE[(i % 6) as usize],
+            )
+        }
+        232.. => {
+            let v = 8 + (i - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+fn nearest_ansi(rgb: (u8, u8, u8)) -> Color {
+    ANSI.iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map(|(color, _)| *color)
+        .expect("ansi colors aren't empty")
+}
+
+fn nearest_indexed(rgb: (u8, u8, u8)) -> Color {
+    let step = |v: u8| {
+        (0..CUBE.len())
+            .min_by_key(|i| (CUBE[*i] as i32 - v as i32).abs())
+            .expect("cube isn't empty") as u8
+    };
+    let cube = 16 + 36 * step(rgb.0) + 6 * step(rgb.1) + step(rgb.2);
+
+    let average = ((rgb.0 as u16 + rgb.1 as u16 + rgb.2 as u16) / 3) as u8;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23);
+
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|i| distance(rgb, indexed_rgb(*i)))
+        .map(Color::Indexed)
+        .expect("candidates aren't empty")
+}
+
+/// rgb values of a color as xterm draws it, `None` for the terminal's default
+pub(crate) fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(i) => Some(indexed_rgb(i)),
+        named => ANSI
+            .iter()
+            .find(|(ansi, _)| *ansi == named)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+impl ColorDepth {
+    /// guesses the depth from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> Self {
+        let var = |name| env::var(name).unwrap_or_default();
+
+        if !var("NO_COLOR").is_empty() || var("TERM") == "dumb" {
+            ColorDepth::Mono
+        } else if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") {
+            ColorDepth::TrueColor
+        } else if var("TERM").contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    /// the closest color this depth can show
+    pub fn degrade(self, color: Color) -> Color {
+        match (self, color) {
+            (_, Color::Reset) | (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Mono, _) => Color::Reset,
+            (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => nearest_indexed((r, g, b)),
+            (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => nearest_ansi((r, g, b)),
+            (ColorDepth::Ansi16, Color::Indexed(i)) => nearest_ansi(indexed_rgb(i)),
+            _ => color,
+        }
+    }
+}
+
+/// colors by name, such as "accent" or "item.raw_iron"
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Theme {
+    colors: BTreeMap<String, Color>,
+}
+
+impl Theme {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn color(mut self, name: impl Into<String>, color: Color) -> Self {
+        self.colors.insert(name.into(), color);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Color> {
+        self.colors.get(name).copied()
+    }
+
+    /// overrides colors with those from `other`
+    pub fn merge(&mut self, other: Theme) {
+        self.colors.extend(other.colors);
+    }
+}
+
+/// theme file contents
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ThemeFile {
+    /// theme to switch to
+    pub theme: Option<String>,
+    /// overrides the detected color depth
+    pub depth: Option<ColorDepth>,
+    /// themes to add, or to merge into existing ones with the same name
+    pub themes: BTreeMap<String, Theme>,
+}
+
+/// every known theme, the current one and the depth colors are degraded to
+///
+/// the first theme added is the base, which colors missing from the current theme fall back to
+#[derive(Debug)]
+pub struct Themes {
+    themes: Vec<(String, Theme)>,
+    current: usize,
+    depth: ColorDepth,
+}
+
+static THEMES: RwLock<Option<Themes>> = RwLock::new(None);
+
+thread_local! {
+    /// themes installed for this thread only, which are read before the global ones
+    static LOCAL: RefCell<Option<Themes>> = const { RefCell::new(None) };
+}
+
+impl Themes {
+    pub fn new(depth: ColorDepth) -> Self {
+        Self {
+            themes: Vec::new(),
+            current: 0,
+            depth,
+        }
+    }
+
+    /// adds a theme, merging it into an existing one with the same name
+    pub fn add(mut self, name: impl Into<String>, theme: Theme) -> Self {
+        let name = name.into();
+        match self.themes.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => existing.merge(theme),
+            None => self.themes.push((name, theme)),
+        }
+        self
+    }
+
+    pub fn apply(mut self, file: ThemeFile) -> Result<Self> {
+        for (name, theme) in file.themes {
+            self = self.add(name, theme);
+        }
+        if let Some(depth) = file.depth {
+            self.depth = depth;
+        }
+        if let Some(theme) = file.theme {
+            self.switch(&theme)?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn switch(&mut self, name: &str) -> Result<()> {
+        self.current = self
+            .themes
+            .iter()
+            .position(|(n, _)| n == name)
+            .ok_or_else(|| eyre!("unknown theme `{}`", name))?;
+        Ok(())
+    }
+
+    /// switches to the next theme, wrapping around, and returns its name
+    pub fn cycle(&mut self) -> Option<&str> {
+        if self.themes.is_empty() {
+            return None;
+        }
+
+        self.current = (self.current + 1) % self.themes.len();
+        self.current()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.themes.get(self.current).map(|(name, _)| name.as_str())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.themes.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn depth(&self) -> ColorDepth {
+        self.depth
+    }
+
+    /// a color from the current theme degraded to the color depth,
+    /// or the default color if no theme names it
+    pub fn get(&self, name: &str) -> Color {
+        let color = [self.themes.get(self.current), self.themes.first()]
+            .into_iter()
+            .flatten()
+            .find_map(|(_, theme)| theme.get(name))
+            .unwrap_or(Color::Reset);
+
+        self.depth.degrade(color)
+    }
+
+    /// makes these the themes [`color`] reads from, replacing any installed before
+    pub fn install(self) {
+        *THEMES.write().expect("themes lock shouldn't be poisoned") = Some(self);
+    }
+
+    /// makes these the themes [`color`] reads from on the current thread until the guard is dropped,
+    /// so tests running in parallel can each use their own
+    pub fn install_local(self) -> LocalThemes {
+        let previous = LOCAL.with_borrow_mut(|local| local.replace(self));
+        LocalThemes { previous }
+    }
+}
+
+/// puts back the thread's previous themes once dropped, see [`Themes::install_local`]
+pub struct LocalThemes {
+    previous: Option<Themes>,
+}
+
+impl Drop for LocalThemes {
+    fn drop(&mut self) {
+        LOCAL.set(self.previous.take());
+    }
+}
+
+/// the themes installed on this thread, or else those installed for every thread
+fn installed<R>(f: impl FnOnce(&Themes) -> R) -> Option<R> {
+    LOCAL.with_borrow(|local| match local {
+        Some(themes) => Some(f(themes)),
+        None => THEMES
+            .read()
+            .expect("themes lock shouldn't be poisoned")
+            .as_ref()
+            .map(f),
+    })
+}
+
+/// a color from the installed themes, the default color if none are installed
+pub fn color(name: &str) -> Color {
+    installed(|themes| themes.get(name)).unwrap_or(Color::Reset)
+}
+
+/// the installed themes' color depth
+pub fn depth() -> Option<ColorDepth> {
+    installed(Themes::depth)
+}
+
+/// a background of `color`, such as a cursor, which is also reversed and bold without colors
+/// so it doesn't disappear once degraded to the default
+pub fn highlight(color: Color) -> Style {
+    let style = Style::new().bg(color);
+    match depth() {
+        Some(ColorDepth::Mono) => style.add_modifier(Modifier::REVERSED | Modifier::BOLD),
+        _ => style,
+    }
+}
+
+/// text in `color`, such as a focused border, which is also bold without colors
+pub fn emphasis(color: Color) -> Style {
+    let style = Style::new().fg(color);
+    match depth() {
+        Some(ColorDepth::Mono) => style.add_modifier(Modifier::BOLD),
+        _ => style,
+    }
+}
+
+/// changes the installed themes, such as switching between them
+pub fn with_themes<R>(f: impl FnOnce(&mut Themes) -> R) -> Option<R> {
+    LOCAL.with_borrow_mut(|local| match local {
+        Some(themes) => Some(f(themes)),
+        None => THEMES
+            .write()
+            .expect("themes lock shouldn't be poisoned")
+            .as_mut()
+            .map(f),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_degrade() {
+        let orange = okabe_ito::ORANGE;
+        assert_eq!(ColorDepth::TrueColor.degrade(orange), orange);
+        assert_eq!(ColorDepth::Ansi256.degrade(orange), Color::Indexed(178));
+        assert_eq!(ColorDepth::Ansi16.degrade(orange), Color::Yellow);
+        assert_eq!(
+            ColorDepth::Ansi16.degrade(Color::Indexed(196)),
+            Color::LightRed
+        );
+        assert_eq!(
+            ColorDepth::Ansi256.degrade(Color::Rgb(128, 128, 128)),
+            Color::Indexed(244),
+            "grays should use the gray ramp"
+        );
+        assert_eq!(ColorDepth::Mono.degrade(Color::Red), Color::Reset);
+        assert_eq!(
+            ColorDepth::Ansi16.degrade(Color::Blue),
+            Color::Blue,
+            "named colors should be kept"
+        );
+    }
+
+    #[test]
+    fn test_themes() {
+        let mut themes = Themes::new(ColorDepth::TrueColor)
+            .add(
+                "default",
+                Theme::new()
+                    .color("accent", Color::Blue)
+                    .color("text", Color::White),
+            )
+            .add("contrast", Theme::new().color("accent", Color::Yellow));
+
+        assert_eq!(themes.get("accent"), Color::Blue);
+        themes.switch("contrast").unwrap();
+        assert_eq!(themes.get("accent"), Color::Yellow);
+        assert_eq!(
+            themes.get("text"),
+            Color::White,
+            "missing colors should fall back to the base theme"
+        );
+        assert_eq!(themes.get("missing"), Color::Reset);
+
+        assert_eq!(themes.cycle(), Some("default"));
+        assert!(themes.switch("nope").is_err());
+    }
+
+    #[test]
+    fn test_local() {
+        let themes = Themes::new(ColorDepth::TrueColor)
+            .add("default", Theme::new().color("accent", Color::Blue));
+        let guard = themes.install_local();
+        assert_eq!(color("accent"), Color::Blue);
+        assert_eq!(highlight(Color::Blue), Style::new().bg(Color::Blue));
+
+        let themes =
+            Themes::new(ColorDepth::Mono).add("default", Theme::new().color("accent", Color::Blue));
+        let mono = themes.install_local();
+        assert_eq!(color("accent"), Color::Reset);
+        assert_eq!(
+            highlight(color("accent")),
+            Style::new()
+                .bg(Color::Reset)
+                .add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            "highlights should stay visible without colors"
+        );
+        assert_eq!(
+            emphasis(Color::Reset),
+            Style::new().fg(Color::Reset).add_modifier(Modifier::BOLD)
+        );
+
+        drop(mono);
+        assert_eq!(
+            depth(),
+            Some(ColorDepth::TrueColor),
+            "previous themes should be put back"
+        );
+        drop(guard);
+        assert!(LOCAL.with_borrow(Option::is_none));
+    }
+
+    #[test]
+    fn test_theme_file() {
+        let file: ThemeFile = serde_json::from_str(
+            r##"{
+                "theme": "mine",
+                "depth": "ansi16",
+                "themes": { "mine": { "accent": "#e69f00" } }
+            }"##,
+        )
+        .unwrap();
+
+        let themes = Themes::new(ColorDepth::TrueColor)
+            .add("default", Theme::new().color("accent", Color::Blue))
+            .apply(file)
+            .unwrap();
+        assert_eq!(themes.current(), Some("mine"));
+        assert_eq!(
+            themes.get("accent"),
+            Color::Yellow,
+            "colors should be degraded to the file's depth"
+        );
+    }
+}
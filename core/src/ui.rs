@@ -0,0 +1,36 @@
+use crossterm::event::KeyEvent;
+
+use crate::control_cluster;
+
+pub mod dialog;
+pub mod input;
+pub mod list;
+pub mod panel;
+pub mod progress;
+
+pub use dialog::{Dialog, DialogMessage};
+pub use input::{Input, InputMessage};
+pub use list::{List, ListMessage};
+pub use panel::Panel;
+pub use progress::Progress;
+
+/// keys shared by every interactive widget
+pub enum UiCluster {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel,
+}
+
+control_cluster!(
+    UiCluster,
+    "ui",
+    (Up, "up", "previous item", ["up"]),
+    (Down, "down", "next item", ["down"]),
+    (Left, "left", "move left", ["left"]),
+    (Right, "right", "move right", ["right"]),
+    (Confirm, "confirm", "confirm", ["enter"]),
+    (Cancel, "cancel", "cancel", ["esc"])
+);
@@ -0,0 +1,160 @@
+use crossterm::event::{Event, KeyCode};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget, Wrap},
+    Frame,
+};
+
+use crate::{
+    component::Component,
+    runtime::RuntimeMessage,
+    util::{controls::ControlCluster, Anchor},
+    AppMessage, Model,
+};
+
+use super::{Panel, UiCluster};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogMessage {
+    Answered(bool),
+}
+
+/// a yes or no question, answered with left and right then confirm, y or n, or cancelled
+pub struct Dialog {
+    text: String,
+    choice: bool,
+    panel: Panel,
+    anchor: Anchor,
+}
+
+impl Dialog {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            choice: false,
+            panel: Panel::new(),
+            anchor: Anchor::default()
+                .percentage(30, 50)
+                .flex_uniform(Flex::Center),
+        }
+    }
+
+    pub fn panel(mut self, panel: Panel) -> Self {
+        self.panel = panel;
+        self
+    }
+
+    /// where the dialog is drawn when used as a model, centered by default
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    fn answer(choice: bool) -> RuntimeMessage<DialogMessage> {
+        RuntimeMessage::App(AppMessage::App(DialogMessage::Answered(choice)))
+    }
+
+    /// the answer a key gives, if any, moving the choice if it doesn't
+    fn key(&mut self, event: &Event) -> Option<RuntimeMessage<DialogMessage>> {
+        let Event::Key(key) = event else {
+            return None;
+        };
+
+        match (UiCluster::contains(key), key.code) {
+            (Some(UiCluster::Left | UiCluster::Right), _) => self.choice = !self.choice,
+            (Some(UiCluster::Confirm), _) => return Some(Self::answer(self.choice)),
+            (Some(UiCluster::Cancel), _) | (_, KeyCode::Char('n')) => {
+                return Some(Self::answer(false));
+            }
+            (_, KeyCode::Char('y')) => return Some(Self::answer(true)),
+            _ => return None,
+        }
+
+        Some(RuntimeMessage::Empty)
+    }
+}
+
+impl Widget for &Dialog {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let selected = Style::new().add_modifier(Modifier::REVERSED);
+        let option = |label, chosen| {
+            Span::styled(
+                format!(" {} ", label),
+                if chosen { selected } else { Style::new() },
+            )
+        };
+
+        self.panel.render(area, buf);
+        let [text, buttons] = Layout::vertical([Constraint::Fill(1), Constraint::Length(1)])
+            .areas(self.panel.inner(area));
+
+        Paragraph::new(self.text.as_str())
+            .centered()
+            .wrap(Wrap { trim: true })
+            .render(text, buf);
+        Line::from(vec![
+            option("yes", self.choice),
+            Span::raw(" "),
+            option("no", !self.choice),
+        ])
+        .centered()
+        .render(buttons, buf);
+    }
+}
+
+impl Model<DialogMessage> for Dialog {
+    fn update(&mut self, message: AppMessage<DialogMessage>) -> RuntimeMessage<DialogMessage> {
+        match message {
+            AppMessage::Event(event) => self.key(&event).unwrap_or(RuntimeMessage::Empty),
+            _ => RuntimeMessage::Empty,
+        }
+    }
+
+    fn view(&mut self, frame: &mut Frame) {
+        let area = self.anchor.compute(frame.area());
+        frame.render_widget(&*self, area);
+    }
+}
+
+impl Component<DialogMessage> for Dialog {
+    fn event(&mut self, event: &Event) -> Option<RuntimeMessage<DialogMessage>> {
+        self.key(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use super::*;
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn test_dialog() {
+        let mut dialog = Dialog::new("quit?");
+        assert!(matches!(
+            dialog.event(&key(KeyCode::Enter)),
+            Some(RuntimeMessage::App(AppMessage::App(DialogMessage::Answered(false))))
+        ));
+
+        dialog.event(&key(KeyCode::Left));
+        assert!(
+            matches!(
+                dialog.event(&key(KeyCode::Enter)),
+                Some(RuntimeMessage::App(AppMessage::App(DialogMessage::Answered(true))))
+            ),
+            "moving the choice should change the answer"
+        );
+        assert!(matches!(
+            dialog.event(&key(KeyCode::Char('n'))),
+            Some(RuntimeMessage::App(AppMessage::App(DialogMessage::Answered(false))))
+        ));
+        assert!(dialog.event(&key(KeyCode::Char('x'))).is_none());
+    }
+}
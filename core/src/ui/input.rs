@@ -0,0 +1,182 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Position, Rect},
+    widgets::{Paragraph, Widget},
+    Frame,
+};
+
+use crate::{
+    component::Component,
+    runtime::RuntimeMessage,
+    util::{controls::ControlCluster, Anchor},
+    AppMessage, Model,
+};
+
+use super::{Panel, UiCluster};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputMessage {
+    Submitted(String),
+}
+
+/// a single line of editable text
+pub struct Input {
+    text: Vec<char>,
+    /// index of the character the cursor is in front of
+    cursor: usize,
+    panel: Panel,
+    anchor: Anchor,
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self {
+            text: Vec::new(),
+            cursor: 0,
+            panel: Panel::new(),
+            anchor: Anchor::default().percentage_uniform(100),
+        }
+    }
+
+    pub fn panel(mut self, panel: Panel) -> Self {
+        self.panel = panel;
+        self
+    }
+
+    /// where the input is drawn when used as a model
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().collect();
+        self.cursor = self.text.len();
+    }
+
+    /// edits the text, returning `None` if the key doesn't edit or submit it
+    fn key(&mut self, key: &KeyEvent) -> Option<RuntimeMessage<InputMessage>> {
+        match (UiCluster::contains(key), key.code) {
+            (Some(UiCluster::Confirm), _) => {
+                let text = self.text();
+                self.set_text("");
+                return Some(RuntimeMessage::App(AppMessage::App(
+                    InputMessage::Submitted(text),
+                )));
+            }
+            (Some(UiCluster::Left), _) => self.cursor = self.cursor.saturating_sub(1),
+            (Some(UiCluster::Right), _) => self.cursor = (self.cursor + 1).min(self.text.len()),
+            (_, KeyCode::Home) => self.cursor = 0,
+            (_, KeyCode::End) => self.cursor = self.text.len(),
+            (_, KeyCode::Backspace) if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            (_, KeyCode::Delete) if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            (_, KeyCode::Char(c))
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            _ => return None,
+        }
+
+        Some(RuntimeMessage::Empty)
+    }
+
+    /// where the terminal cursor should be shown when the input is drawn in `area`
+    pub fn cursor_position(&self, area: Rect) -> Position {
+        let inner = self.panel.inner(area);
+        let offset = self.cursor.saturating_sub(inner.width.saturating_sub(1) as usize);
+
+        Position::new(inner.x + (self.cursor - offset) as u16, inner.y)
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Widget for &Input {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.panel.inner(area);
+
+        // scrolls horizontally to keep the cursor visible
+        let offset = self.cursor.saturating_sub(inner.width.saturating_sub(1) as usize);
+        let text: String = self.text.iter().skip(offset).collect();
+
+        self.panel.render_with(area, buf, Paragraph::new(text));
+    }
+}
+
+impl Model<InputMessage> for Input {
+    fn update(&mut self, message: AppMessage<InputMessage>) -> RuntimeMessage<InputMessage> {
+        match message {
+            AppMessage::Event(Event::Key(key)) => self.key(&key).unwrap_or(RuntimeMessage::Empty),
+            _ => RuntimeMessage::Empty,
+        }
+    }
+
+    fn view(&mut self, frame: &mut Frame) {
+        let area = self.anchor.compute(frame.area());
+        frame.render_widget(&*self, area);
+        frame.set_cursor_position(self.cursor_position(area));
+    }
+}
+
+impl Component<InputMessage> for Input {
+    fn event(&mut self, event: &Event) -> Option<RuntimeMessage<InputMessage>> {
+        match event {
+            Event::Key(key) => self.key(key),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input() {
+        let mut input = Input::new();
+        for code in [
+            KeyCode::Char('a'),
+            KeyCode::Char('c'),
+            KeyCode::Left,
+            KeyCode::Char('b'),
+            KeyCode::End,
+            KeyCode::Backspace,
+        ] {
+            input.event(&Event::Key(KeyEvent::from(code)));
+        }
+        assert_eq!(input.text(), "ab");
+
+        assert!(
+            input
+                .event(&Event::Key(KeyEvent::new(
+                    KeyCode::Char('c'),
+                    KeyModifiers::CONTROL
+                )))
+                .is_none(),
+            "shortcuts should bubble instead of being typed"
+        );
+        assert!(matches!(
+            input.event(&Event::Key(KeyEvent::from(KeyCode::Enter))),
+            Some(RuntimeMessage::App(AppMessage::App(InputMessage::Submitted(text)))) if text == "ab"
+        ));
+        assert_eq!(input.text(), "", "submitting should clear the input");
+    }
+}
@@ -0,0 +1,171 @@
+use crossterm::event::Event;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Paragraph, Widget},
+    Frame,
+};
+
+use crate::{
+    component::Component,
+    runtime::RuntimeMessage,
+    util::{controls::ControlCluster, Anchor},
+    AppMessage, Model,
+};
+
+use super::{Panel, UiCluster};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListMessage {
+    /// the item at this index was confirmed
+    Chosen(usize),
+}
+
+/// a panel of items, one of which is selected
+pub struct List {
+    items: Vec<Line<'static>>,
+    selected: usize,
+    panel: Panel,
+    anchor: Anchor,
+}
+
+impl List {
+    pub fn new(items: impl IntoIterator<Item = impl Into<Line<'static>>>) -> Self {
+        Self {
+            items: items.into_iter().map(Into::into).collect(),
+            selected: 0,
+            panel: Panel::new(),
+            anchor: Anchor::default().percentage_uniform(100),
+        }
+    }
+
+    pub fn panel(mut self, panel: Panel) -> Self {
+        self.panel = panel;
+        self
+    }
+
+    /// where the list is drawn when used as a model
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        (!self.items.is_empty()).then_some(self.selected)
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+    }
+
+    pub fn set_items(&mut self, items: impl IntoIterator<Item = impl Into<Line<'static>>>) {
+        self.items = items.into_iter().map(Into::into).collect();
+        self.select(self.selected);
+    }
+}
+
+impl Widget for &List {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.panel.inner(area);
+
+        // scrolls just far enough to keep the selection visible
+        let height = (inner.height as usize).max(1);
+        let offset = self.selected.saturating_sub(height - 1);
+
+        let lines: Vec<_> = self
+            .items
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(i, item)| {
+                if i == self.selected {
+                    item.clone().patch_style(Style::new().add_modifier(Modifier::REVERSED))
+                } else {
+                    item.clone()
+                }
+            })
+            .collect();
+
+        self.panel.render_with(area, buf, Paragraph::new(lines));
+    }
+}
+
+impl Model<ListMessage> for List {
+    fn update(&mut self, message: AppMessage<ListMessage>) -> RuntimeMessage<ListMessage> {
+        let AppMessage::Event(Event::Key(event)) = message else {
+            return RuntimeMessage::Empty;
+        };
+
+        match UiCluster::contains(&event) {
+            Some(UiCluster::Up) => self.select(self.selected.saturating_sub(1)),
+            Some(UiCluster::Down) => self.select(self.selected + 1),
+            Some(UiCluster::Confirm) => {
+                if let Some(selected) = self.selected() {
+                    return RuntimeMessage::App(AppMessage::App(ListMessage::Chosen(selected)));
+                }
+            }
+            _ => (),
+        }
+
+        RuntimeMessage::Empty
+    }
+
+    fn view(&mut self, frame: &mut Frame) {
+        let area = self.anchor.compute(frame.area());
+        frame.render_widget(&*self, area);
+    }
+}
+
+impl Component<ListMessage> for List {
+    fn event(&mut self, event: &Event) -> Option<RuntimeMessage<ListMessage>> {
+        match event {
+            Event::Key(key) => match UiCluster::contains(key) {
+                Some(UiCluster::Up | UiCluster::Down | UiCluster::Confirm) => {
+                    Some(self.update(AppMessage::Event(event.clone())))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use futures::executor::block_on;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::runtime::headless::headless;
+
+    #[test]
+    fn test_list() {
+        let mut list = List::new(["a", "b", "c"]);
+        let key = |code| Event::Key(KeyEvent::from(code));
+
+        let buffers = block_on(headless(
+            &mut list,
+            [key(KeyCode::Down), key(KeyCode::Down), key(KeyCode::Down)],
+            TestBackend::new(5, 3),
+        ))
+        .expect("headless run should succeed");
+        assert_eq!(list.selected(), Some(2), "selection should stop at the end");
+        assert_eq!(
+            buffers[2].cell((1, 1)).map(|cell| cell.symbol()),
+            Some("c"),
+            "list should scroll to keep the selection visible"
+        );
+
+        assert!(matches!(
+            list.update(AppMessage::Event(key(KeyCode::Enter))),
+            RuntimeMessage::App(AppMessage::App(ListMessage::Chosen(2)))
+        ));
+        assert!(
+            list.event(&key(KeyCode::Esc)).is_none(),
+            "unbound keys should bubble"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    widgets::{Block, BorderType, Clear, Widget},
+};
+
+/// a titled, bordered box which other widgets are drawn inside of
+#[derive(Default, Clone)]
+pub struct Panel {
+    title: Option<String>,
+    style: Style,
+    focused: bool,
+}
+
+impl Panel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// style of the border and title
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// draws a thicker, bold border to show the panel receives key presses
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    pub fn block(&self) -> Block<'static> {
+        let mut block = Block::bordered()
+            .border_type(BorderType::Rounded)
+            .border_style(self.style);
+
+        if self.focused {
+            block = block
+                .border_type(BorderType::Thick)
+                .border_style(self.style.add_modifier(Modifier::BOLD));
+        }
+        if let Some(title) = &self.title {
+            block = block.title(format!(" {} ", title));
+        }
+
+        block
+    }
+
+    /// area left for the contents
+    pub fn inner(&self, area: Rect) -> Rect {
+        self.block().inner(area)
+    }
+
+    /// clears the area, then draws the panel with `widget` inside of it
+    pub fn render_with(&self, area: Rect, buf: &mut Buffer, widget: impl Widget) {
+        Clear.render(area, buf);
+        self.block().render(area, buf);
+        widget.render(self.inner(area), buf);
+    }
+}
+
+impl Widget for &Panel {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+        self.block().render(area, buf);
+    }
+}
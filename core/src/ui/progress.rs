@@ -0,0 +1,75 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+/// a single line bar filled up to a ratio, with an optional label in front
+pub struct Progress {
+    ratio: f64,
+    label: Option<String>,
+    style: Style,
+}
+
+impl Progress {
+    /// `ratio` is clamped between 0 and 1
+    pub fn new(ratio: f64) -> Self {
+        Self {
+            ratio: ratio.clamp(0., 1.),
+            label: None,
+            style: Style::new(),
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// style of the filled part of the bar
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl Widget for Progress {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let percent = format!(" {:>3}%", (self.ratio * 100.).round() as u8);
+        let label = self.label.map(|label| format!("{} ", label)).unwrap_or_default();
+
+        let width = (area.width as usize).saturating_sub(label.chars().count() + percent.len());
+        let filled = (width as f64 * self.ratio).round() as usize;
+
+        Line::from(vec![
+            Span::raw(label),
+            Span::styled("█".repeat(filled), self.style),
+            Span::raw("░".repeat(width - filled)),
+            Span::raw(percent),
+        ])
+        .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        let area = Rect::new(0, 0, 14, 1);
+        let mut buf = Buffer::empty(area);
+        Progress::new(0.5).label("io").render(area, &mut buf);
+        assert_eq!(buf, Buffer::with_lines(["io ███░░░  50%"]));
+
+        let mut buf = Buffer::empty(area);
+        Progress::new(2.).render(area, &mut buf);
+        assert_eq!(
+            buf,
+            Buffer::with_lines(["█████████ 100%"]),
+            "ratio should be clamped"
+        );
+    }
+}
@@ -1,8 +1,10 @@
-use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use crossterm::event::MouseEvent;
+use ratatui::layout::{Constraint, Flex, Layout, Position, Rect};
 
 use crate::{runtime::RuntimeMessage, AppMessage};
 
 pub mod controls;
+pub mod help;
 
 #[macro_export]
 macro_rules! passthru {
@@ -20,6 +22,24 @@ pub fn app_message<T>(msg: T) -> RuntimeMessage<T> {
     RuntimeMessage::App(AppMessage::App(msg))
 }
 
+/// position of a mouse event relative to the top left of `area`, if it lies within it
+pub fn hit_test(area: Rect, event: &MouseEvent) -> Option<Position> {
+    let position = Position::new(event.column, event.row);
+    if !area.contains(position) {
+        return None;
+    }
+
+    Some(Position::new(position.x - area.x, position.y - area.y))
+}
+
+/// index of the first area containing a mouse event, along with the relative position inside it
+pub fn hit_test_any(areas: &[Rect], event: &MouseEvent) -> Option<(usize, Position)> {
+    areas
+        .iter()
+        .enumerate()
+        .find_map(|(i, &area)| Some((i, hit_test(area, event)?)))
+}
+
 #[derive(Default)]
 pub struct Anchor {
     fv: Flex,
@@ -63,3 +83,44 @@ impl Anchor {
         area
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEventKind};
+
+    use super::*;
+
+    fn click(column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn test_hit_test() {
+        let area = Anchor::default()
+            .percentage_uniform(50)
+            .flex_uniform(Flex::Center)
+            .compute(Rect::new(0, 0, 20, 10));
+
+        assert_eq!(
+            hit_test(area, &click(area.x + 2, area.y + 1)),
+            Some(Position::new(2, 1))
+        );
+        assert_eq!(
+            hit_test(area, &click(0, 0)),
+            None,
+            "click outside the area should miss"
+        );
+
+        let areas = [Rect::new(0, 0, 5, 1), Rect::new(5, 0, 5, 1)];
+        assert_eq!(
+            hit_test_any(&areas, &click(7, 0)),
+            Some((1, Position::new(2, 0)))
+        );
+        assert_eq!(hit_test_any(&areas, &click(7, 1)), None);
+    }
+}
@@ -1,44 +1,626 @@
-use crossterm::event::KeyEvent;
-use std::collections::HashSet;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::{eyre, Result, WrapErr};
+use log::error;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
 
+pub mod sequence;
+
+/// drops event kind and state, along with shift on symbols
+/// since terminals disagree on whether "?" is reported as shifted
 fn strip(event: &KeyEvent) -> KeyEvent {
-    KeyEvent::new(event.code, event.modifiers)
+    let mut modifiers = event.modifiers;
+    if let KeyCode::Char(c) = event.code
+        && !c.is_alphabetic()
+    {
+        modifiers.remove(KeyModifiers::SHIFT);
+    }
+
+    KeyEvent::new(event.code, modifiers)
+}
+
+fn parse_modifier(modifier: &str) -> Result<KeyModifiers> {
+    Ok(match modifier.to_lowercase().as_str() {
+        "ctrl" | "control" => KeyModifiers::CONTROL,
+        "shift" => KeyModifiers::SHIFT,
+        "alt" => KeyModifiers::ALT,
+        "super" => KeyModifiers::SUPER,
+        "hyper" => KeyModifiers::HYPER,
+        "meta" => KeyModifiers::META,
+        _ => return Err(eyre!("unknown modifier `{}`", modifier)),
+    })
+}
+
+fn parse_code(code: &str) -> Result<KeyCode> {
+    let mut chars = code.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+
+    let lower = code.to_lowercase();
+    Ok(match lower.as_str() {
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => match lower.strip_prefix('f').map(str::parse) {
+            Some(Ok(n @ 1..=24)) => KeyCode::F(n),
+            _ => return Err(eyre!("unknown key `{}`", code)),
+        },
+    })
+}
+
+/// parses a binding such as "h", "ctrl+c" or "shift+left"
+///
+/// shifted letters are normalized the way terminals report them,
+/// so "shift+a" and "A" are the same binding
+pub fn parse_key(binding: &str) -> Result<KeyEvent> {
+    let (modifiers, code) = if binding == "+" {
+        ("", "+")
+    } else if let Some(modifiers) = binding.strip_suffix("++") {
+        (modifiers, "+")
+    } else {
+        binding.rsplit_once('+').unwrap_or(("", binding))
+    };
+
+    if code.is_empty() {
+        return Err(eyre!("binding `{}` has no key", binding));
+    }
+
+    let mut modifiers = modifiers
+        .split('+')
+        .filter(|m| !m.is_empty())
+        .map(parse_modifier)
+        .collect::<Result<KeyModifiers>>()
+        .wrap_err_with(|| format!("invalid binding `{}`", binding))?;
+    let mut code = parse_code(code).wrap_err_with(|| format!("invalid binding `{}`", binding))?;
+
+    if let KeyCode::Char(c) = code {
+        if c.is_uppercase() {
+            modifiers |= KeyModifiers::SHIFT;
+        } else if modifiers.contains(KeyModifiers::SHIFT) {
+            code = KeyCode::Char(c.to_ascii_uppercase());
+        }
+    }
+
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// parses a binding which may be a sequence of keys separated by spaces, such as "g g"
+pub fn parse_binding(binding: &str) -> Result<Vec<KeyEvent>> {
+    let keys = binding
+        .split_whitespace()
+        .map(parse_key)
+        .collect::<Result<Vec<_>>>()?;
+
+    if keys.is_empty() {
+        return Err(eyre!("binding is empty"));
+    }
+
+    Ok(keys)
+}
+
+/// formats a key the same way bindings are written
+pub fn format_key(key: &KeyEvent) -> String {
+    let mut out = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "ctrl"),
+        (KeyModifiers::ALT, "alt"),
+        (KeyModifiers::SUPER, "super"),
+        (KeyModifiers::HYPER, "hyper"),
+        (KeyModifiers::META, "meta"),
+    ] {
+        if key.modifiers.contains(modifier) {
+            out.push_str(name);
+            out.push('+');
+        }
+    }
+
+    // shift is implied by uppercase letters
+    let shifted_char = matches!(key.code, KeyCode::Char(c) if c.is_uppercase());
+    if key.modifiers.contains(KeyModifiers::SHIFT) && !shifted_char {
+        out.push_str("shift+");
+    }
+
+    match key.code {
+        KeyCode::Char(' ') => out.push_str("space"),
+        KeyCode::Char(c) => out.push(c),
+        KeyCode::F(n) => out.push_str(&format!("f{}", n)),
+        KeyCode::PageUp => out.push_str("pageup"),
+        KeyCode::PageDown => out.push_str("pagedown"),
+        KeyCode::BackTab => out.push_str("backtab"),
+        code => out.push_str(&format!("{:?}", code).to_lowercase()),
+    }
+
+    out
+}
+
+pub fn format_binding(keys: &[KeyEvent]) -> String {
+    keys.iter().map(format_key).collect::<Vec<_>>().join(" ")
 }
 
-#[derive(Default)]
+/// how a sequence of keys matches a set of bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Match<A> {
+    None,
+    /// the keys start a binding, but more are needed
+    Prefix,
+    Exact(A),
+}
+
+impl<A> Match<A> {
+    pub fn map<B>(self, f: impl FnOnce(A) -> B) -> Match<B> {
+        match self {
+            Match::None => Match::None,
+            Match::Prefix => Match::Prefix,
+            Match::Exact(a) => Match::Exact(f(a)),
+        }
+    }
+
+    /// combines matches from two sets, preferring an exact match
+    pub fn or(self, other: Match<A>) -> Match<A> {
+        match (self, other) {
+            (Match::Exact(a), _) | (_, Match::Exact(a)) => Match::Exact(a),
+            (Match::Prefix, _) | (_, Match::Prefix) => Match::Prefix,
+            _ => Match::None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct ControlSet {
-    set: HashSet<KeyEvent>,
+    bindings: Vec<Vec<KeyEvent>>,
 }
 
 impl ControlSet {
-    pub fn new(keys: &Vec<KeyEvent>) -> Self {
-        Self {
-            set: keys.iter().map(|e| strip(e)).collect(),
+    pub fn new(keys: &[KeyEvent]) -> Self {
+        Self::from_bindings(keys.iter().map(|key| vec![*key]))
+    }
+
+    /// builds a set from bindings such as "ctrl+c" or "g g", see [`parse_binding`]
+    pub fn parse(bindings: &[impl AsRef<str>]) -> Result<Self> {
+        let bindings = bindings
+            .iter()
+            .map(|binding| parse_binding(binding.as_ref()))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self::from_bindings(bindings))
+    }
+
+    /// keeps the order bindings were given in, so they can be displayed that way
+    fn from_bindings(bindings: impl IntoIterator<Item = Vec<KeyEvent>>) -> Self {
+        let mut set = Self::default();
+        for binding in bindings {
+            let binding: Vec<_> = binding.iter().map(strip).collect();
+            if !set.bindings.contains(&binding) {
+                set.bindings.push(binding);
+            }
         }
+
+        set
     }
 
     pub fn contains(&self, key: &KeyEvent) -> bool {
-        self.set.contains(&strip(key))
+        matches!(self.matches(&[*key]), Match::Exact(()))
+    }
+
+    pub fn matches(&self, keys: &[KeyEvent]) -> Match<()> {
+        let keys: Vec<_> = keys.iter().map(strip).collect();
+        if self.bindings.contains(&keys) {
+            Match::Exact(())
+        } else if self.bindings.iter().any(|binding| binding.starts_with(&keys)) {
+            Match::Prefix
+        } else {
+            Match::None
+        }
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = &[KeyEvent]> {
+        self.bindings.iter().map(Vec::as_slice)
     }
 }
 
+/// an action of a cluster, as declared in [`control_cluster!`](crate::control_cluster)
+#[derive(Debug, Clone, Copy)]
+pub struct ActionInfo {
+    /// name in keymap files
+    pub name: &'static str,
+    /// description shown to players
+    pub label: &'static str,
+    pub defaults: &'static [&'static str],
+}
+
 pub trait ControlCluster {
-    fn contains(event: &KeyEvent) -> Option<Self>
+    /// name of the cluster in keymap files
+    const NAME: &'static str;
+
+    /// every action in the cluster, in declaration order
+    fn actions() -> &'static [ActionInfo];
+
+    /// matches keys typed so far, see [`Sequence`](sequence::Sequence) for feeding them
+    fn matches(keys: &[KeyEvent]) -> Match<Self>
     where
         Self: Sized;
+
+    /// matches a single key press
+    fn contains(event: &KeyEvent) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        match Self::matches(&[*event]) {
+            Match::Exact(action) => Some(action),
+            _ => None,
+        }
+    }
+}
+
+/// keymap file contents, bindings by action by cluster
+pub type Overrides = HashMap<String, HashMap<String, Vec<String>>>;
+
+/// a binding shared by more than one action, or a prefix of another action's sequence
+/// which would complete before the sequence could be typed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub keys: Vec<KeyEvent>,
+    /// `cluster.action` names
+    pub actions: Vec<String>,
 }
 
+/// an action along with its current bindings
+#[derive(Debug, Clone)]
+pub struct Bound {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub set: ControlSet,
+}
+
+/// bindings for every registered cluster, in registration order
+#[derive(Debug, Default)]
+pub struct Keymap {
+    clusters: Vec<(&'static str, Vec<Bound>)>,
+}
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+impl Keymap {
+    /// adds a cluster with its default bindings, replacing one with the same name
+    pub fn register<C: ControlCluster>(mut self) -> Result<Self> {
+        let actions = C::actions()
+            .iter()
+            .map(|action| {
+                let set = ControlSet::parse(action.defaults)
+                    .wrap_err_with(|| format!("default binding for {}.{}", C::NAME, action.name))?;
+                Ok(Bound {
+                    name: action.name,
+                    label: action.label,
+                    set,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        match self.cluster_mut(C::NAME) {
+            Some(existing) => *existing = actions,
+            None => self.clusters.push((C::NAME, actions)),
+        }
+        Ok(self)
+    }
+
+    fn cluster_mut(&mut self, cluster: &str) -> Option<&mut Vec<Bound>> {
+        self.clusters
+            .iter_mut()
+            .find(|(name, _)| *name == cluster)
+            .map(|(_, actions)| actions)
+    }
+
+    /// replaces the bindings of every action listed in `overrides`
+    pub fn apply(&mut self, overrides: &Overrides) -> Result<()> {
+        for (cluster, actions) in overrides {
+            let bound = self
+                .cluster_mut(cluster)
+                .ok_or_else(|| eyre!("unknown control cluster `{}`", cluster))?;
+
+            for (action, bindings) in actions {
+                let bound = bound
+                    .iter_mut()
+                    .find(|bound| bound.name == action)
+                    .ok_or_else(|| eyre!("unknown action `{}.{}`", cluster, action))?;
+                bound.set = ControlSet::parse(bindings)
+                    .wrap_err_with(|| format!("in `{}.{}`", cluster, action))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, cluster: &str, action: &str) -> Option<&ControlSet> {
+        self.clusters()
+            .find(|(name, _)| *name == cluster)?
+            .1
+            .iter()
+            .find(|bound| bound.name == action)
+            .map(|bound| &bound.set)
+    }
+
+    pub fn clusters(&self) -> impl Iterator<Item = (&'static str, &[Bound])> {
+        self.clusters
+            .iter()
+            .map(|(name, actions)| (*name, actions.as_slice()))
+    }
+
+    /// conflicting bindings across every cluster, sorted by binding
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let bindings: Vec<_> = self
+            .clusters()
+            .flat_map(|(cluster, actions)| {
+                actions.iter().flat_map(move |bound| {
+                    bound
+                        .set
+                        .bindings()
+                        .map(move |keys| (keys, format!("{}.{}", cluster, bound.name)))
+                })
+            })
+            .collect();
+
+        let mut conflicts = BTreeMap::new();
+        for (keys, _) in &bindings {
+            let mut actions: Vec<_> = bindings
+                .iter()
+                .filter(|(other, _)| other.starts_with(keys))
+                .map(|(_, action)| action.clone())
+                .collect();
+            actions.sort();
+            actions.dedup();
+
+            if actions.len() > 1 {
+                conflicts.insert(
+                    format_binding(keys),
+                    Conflict {
+                        keys: keys.to_vec(),
+                        actions,
+                    },
+                );
+            }
+        }
+
+        conflicts.into_values().collect()
+    }
+
+    /// makes this the keymap every cluster is matched against, which can only happen once
+    pub fn install(self) -> Result<()> {
+        KEYMAP
+            .set(self)
+            .map_err(|_| eyre!("a keymap is already installed"))
+    }
+
+    pub fn installed() -> Option<&'static Keymap> {
+        KEYMAP.get()
+    }
+}
+
+/// how `keys` match an action, using the installed keymap
+/// or the default bindings if none was installed, which are parsed into `parsed` once
+///
+/// defaults are checked when their cluster is [registered](Keymap::register), so a bad one
+/// fails at startup, and is left unbound here rather than failing on a key press
+pub fn bound(
+    cluster: &str,
+    action: &str,
+    defaults: &[&str],
+    parsed: &OnceLock<ControlSet>,
+    keys: &[KeyEvent],
+) -> Match<()> {
+    match KEYMAP.get().and_then(|keymap| keymap.get(cluster, action)) {
+        Some(set) => set.matches(keys),
+        None => parsed
+            .get_or_init(|| {
+                ControlSet::parse(defaults).unwrap_or_else(|err| {
+                    error!("default binding for {}.{}: {}", cluster, action, err);
+                    ControlSet::default()
+                })
+            })
+            .matches(keys),
+    }
+}
+
+/// implements [`ControlCluster`] for an enum, giving every variant a name, a label and default bindings
 #[macro_export]
 macro_rules! control_cluster {
-    ($cluster:ident, $(($path:ident, $set:expr)), *) => {
-        impl cog_core::util::controls::ControlCluster for $cluster {
-            fn contains(event: &KeyEvent) -> Option<Self> {
-                $(if $set.contains(event) {
-                    return Some($cluster::$path);
+    ($cluster:ident, $name:literal, $(($path:ident, $action:literal, $label:literal, [$($key:literal),*])), *) => {
+        impl $crate::util::controls::ControlCluster for $cluster {
+            const NAME: &'static str = $name;
+
+            fn actions() -> &'static [$crate::util::controls::ActionInfo] {
+                &[$($crate::util::controls::ActionInfo {
+                    name: $action,
+                    label: $label,
+                    defaults: &[$($key),*],
+                }),*]
+            }
+
+            fn matches(keys: &[KeyEvent]) -> $crate::util::controls::Match<Self> {
+                use std::sync::OnceLock;
+                use $crate::util::controls::{bound, ControlSet, Match};
+
+                let mut prefix = false;
+                $(match {
+                    static PARSED: OnceLock<ControlSet> = OnceLock::new();
+                    bound($name, $action, &[$($key),*], &PARSED, keys)
+                } {
+                    Match::Exact(()) => return Match::Exact($cluster::$path),
+                    Match::Prefix => prefix = true,
+                    Match::None => (),
                 })*
 
-                None
+                if prefix { Match::Prefix } else { Match::None }
             }
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    enum Movement {
+        Left,
+        Right,
+    }
+
+    control_cluster!(
+        Movement,
+        "movement",
+        (Left, "left", "move left", ["left", "h"]),
+        (Right, "right", "move right", ["right", "l"])
+    );
+
+    enum Action {
+        Exit,
+    }
+
+    control_cluster!(Action, "action", (Exit, "exit", "quit", ["ctrl+c", "q"]));
+
+    enum Broken {
+        Nope,
+    }
+
+    control_cluster!(Broken, "broken", (Nope, "nope", "nothing", ["ctrl+nope"]));
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(
+            parse_key("ctrl+c").unwrap(),
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_key("shift+left").unwrap(),
+            KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse_key("shift+a").unwrap(),
+            parse_key("A").unwrap(),
+            "shifted letters should be normalized"
+        );
+        assert_eq!(
+            parse_key("ctrl++").unwrap(),
+            KeyEvent::new(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(parse_key("f5").unwrap().code, KeyCode::F(5));
+        assert!(
+            ControlSet::parse(&["?"])
+                .unwrap()
+                .contains(&KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT)),
+            "shifted symbols should match unshifted bindings"
+        );
+
+        let err = parse_key("ctrl+nope").unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("unknown key `nope`"),
+            "error should name the bad key: {:#}",
+            err
+        );
+        assert!(parse_key("hyperr+a").is_err());
+        assert!(parse_key("ctrl+").is_err());
+    }
+
+    #[test]
+    fn test_bad_default() {
+        assert!(
+            Keymap::default().register::<Broken>().is_err(),
+            "bad defaults should fail when registered"
+        );
+        assert!(
+            Broken::contains(&KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL)).is_none(),
+            "bad defaults should be left unbound"
+        );
+    }
+
+    #[test]
+    fn test_format_key() {
+        for binding in ["ctrl+c", "shift+left", "A", "space", "f12", "alt+enter"] {
+            assert_eq!(
+                format_key(&parse_key(binding).unwrap()),
+                binding,
+                "formatting should roundtrip"
+            );
+        }
+    }
+
+    #[test]
+    fn test_keymap() {
+        let mut keymap = Keymap::default()
+            .register::<Movement>()
+            .unwrap()
+            .register::<Action>()
+            .unwrap();
+        assert!(keymap.conflicts().is_empty());
+
+        let overrides: Overrides = HashMap::from([(
+            "movement".to_string(),
+            HashMap::from([("left".to_string(), vec!["q".to_string()])]),
+        )]);
+        keymap.apply(&overrides).unwrap();
+
+        let left = keymap.get("movement", "left").unwrap();
+        assert!(left.contains(&parse_key("q").unwrap()));
+        assert!(
+            !left.contains(&parse_key("h").unwrap()),
+            "overrides should replace the defaults"
+        );
+        assert_eq!(
+            keymap.conflicts(),
+            vec![Conflict {
+                keys: vec![parse_key("q").unwrap()],
+                actions: vec!["action.exit".to_string(), "movement.left".to_string()],
+            }]
+        );
+
+        let unknown: Overrides = HashMap::from([("nope".to_string(), HashMap::new())]);
+        assert!(keymap.apply(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_prefix_conflict() {
+        let mut keymap = Keymap::default().register::<Movement>().unwrap();
+        let overrides: Overrides = HashMap::from([(
+            "movement".to_string(),
+            HashMap::from([
+                ("left".to_string(), vec!["g".to_string()]),
+                ("right".to_string(), vec!["g l".to_string()]),
+            ]),
+        )]);
+        keymap.apply(&overrides).unwrap();
+
+        assert_eq!(
+            keymap.conflicts(),
+            vec![Conflict {
+                keys: vec![parse_key("g").unwrap()],
+                actions: vec!["movement.left".to_string(), "movement.right".to_string()],
+            }],
+            "a binding shadowing a sequence should conflict"
+        );
+    }
+
+    #[test]
+    fn test_cluster() {
+        // no keymap is installed in tests, so the defaults apply
+        assert!(matches!(
+            Movement::contains(&parse_key("h").unwrap()),
+            Some(Movement::Left)
+        ));
+        assert!(Movement::contains(&parse_key("ctrl+h").unwrap()).is_none());
+    }
+}
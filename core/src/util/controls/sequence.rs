@@ -0,0 +1,177 @@
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::{format_binding, strip, Match};
+
+/// result of feeding a key to a [`Sequence`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step<A> {
+    /// a binding was completed, along with its count prefix or 1 if there was none
+    Complete(A, usize),
+    /// the key started or continued a sequence or count
+    Pending,
+    /// the key didn't continue the pending sequence, which was dropped along with it
+    Cancelled,
+    /// nothing was pending and the key doesn't start a binding
+    Unmatched,
+}
+
+/// matches multi-key bindings such as "g g" one key at a time, with an optional count like "5 l"
+///
+/// a sequence doesn't keep time, so a model should call [`Sequence::expire`]
+/// once [`Sequence::timeout`] has passed without a key, usually through a subscription
+pub struct Sequence {
+    keys: Vec<KeyEvent>,
+    count: Option<usize>,
+    timeout: Duration,
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+impl Sequence {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            keys: Vec::new(),
+            count: None,
+            timeout,
+        }
+    }
+
+    /// how long a pending sequence should be kept between keys
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.keys.is_empty() || self.count.is_some()
+    }
+
+    /// the count and keys typed so far, for display
+    pub fn pending(&self) -> Option<String> {
+        if !self.is_pending() {
+            return None;
+        }
+
+        let count = self.count.map(|count| count.to_string());
+        let keys = (!self.keys.is_empty()).then(|| format_binding(&self.keys));
+
+        Some(count.into_iter().chain(keys).collect::<Vec<_>>().join(" "))
+    }
+
+    /// drops the pending sequence
+    pub fn expire(&mut self) {
+        self.keys.clear();
+        self.count = None;
+    }
+
+    /// digit continuing a count, a count can't start with 0
+    fn digit(&self, event: &KeyEvent) -> Option<usize> {
+        let KeyCode::Char(c) = event.code else {
+            return None;
+        };
+        if event.modifiers != KeyModifiers::NONE {
+            return None;
+        }
+
+        c.to_digit(10)
+            .filter(|d| *d != 0 || self.count.is_some())
+            .map(|d| d as usize)
+    }
+
+    /// adds a key to the pending sequence, `matches` usually being [`ControlCluster::matches`](super::ControlCluster::matches)
+    /// or several of them combined with [`Match::or`]
+    pub fn feed<A>(
+        &mut self,
+        event: &KeyEvent,
+        matches: impl FnOnce(&[KeyEvent]) -> Match<A>,
+    ) -> Step<A> {
+        let was_pending = self.is_pending();
+
+        self.keys.push(strip(event));
+        match matches(&self.keys) {
+            Match::Exact(action) => {
+                let count = self.count.take().unwrap_or(1);
+                self.keys.clear();
+                Step::Complete(action, count)
+            }
+            Match::Prefix => Step::Pending,
+            Match::None => {
+                self.keys.pop();
+
+                // digits only count when they aren't bound themselves
+                if self.keys.is_empty()
+                    && let Some(digit) = self.digit(event)
+                {
+                    let count = self.count.unwrap_or(0);
+                    self.count = Some(count.saturating_mul(10).saturating_add(digit));
+                    return Step::Pending;
+                }
+
+                self.expire();
+                if was_pending {
+                    Step::Cancelled
+                } else {
+                    Step::Unmatched
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::controls::{parse_key, ControlSet};
+
+    fn feed(sequence: &mut Sequence, set: &ControlSet, binding: &str) -> Step<()> {
+        sequence.feed(&parse_key(binding).unwrap(), |keys| set.matches(keys))
+    }
+
+    #[test]
+    fn test_sequence() {
+        let set = ControlSet::parse(&["g g", "l"]).unwrap();
+        let mut sequence = Sequence::default();
+
+        assert_eq!(feed(&mut sequence, &set, "g"), Step::Pending);
+        assert_eq!(sequence.pending().as_deref(), Some("g"));
+        assert_eq!(feed(&mut sequence, &set, "g"), Step::Complete((), 1));
+        assert!(!sequence.is_pending());
+
+        assert_eq!(feed(&mut sequence, &set, "g"), Step::Pending);
+        assert_eq!(
+            feed(&mut sequence, &set, "x"),
+            Step::Cancelled,
+            "a key breaking the sequence should drop it"
+        );
+        assert_eq!(feed(&mut sequence, &set, "x"), Step::Unmatched);
+
+        assert_eq!(feed(&mut sequence, &set, "g"), Step::Pending);
+        sequence.expire();
+        assert_eq!(feed(&mut sequence, &set, "l"), Step::Complete((), 1));
+    }
+
+    #[test]
+    fn test_count() {
+        let set = ControlSet::parse(&["l", "g g"]).unwrap();
+        let mut sequence = Sequence::default();
+
+        assert_eq!(
+            feed(&mut sequence, &set, "0"),
+            Step::Unmatched,
+            "counts can't start with 0"
+        );
+        assert_eq!(feed(&mut sequence, &set, "1"), Step::Pending);
+        assert_eq!(feed(&mut sequence, &set, "2"), Step::Pending);
+        assert_eq!(feed(&mut sequence, &set, "g"), Step::Pending);
+        assert_eq!(sequence.pending().as_deref(), Some("12 g"));
+        assert_eq!(feed(&mut sequence, &set, "g"), Step::Complete((), 12));
+
+        assert_eq!(feed(&mut sequence, &set, "5"), Step::Pending);
+        assert_eq!(feed(&mut sequence, &set, "l"), Step::Complete((), 5));
+    }
+}
@@ -0,0 +1,109 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Cell, Clear, Row, Table, Widget},
+};
+
+use crate::ui::Panel;
+
+use super::controls::{format_binding, Keymap};
+
+/// lists every action of every cluster in a keymap along with its bindings
+pub struct HelpWidget<'a> {
+    keymap: &'a Keymap,
+    style: Style,
+}
+
+impl<'a> HelpWidget<'a> {
+    pub fn new(keymap: &'a Keymap) -> Self {
+        Self {
+            keymap,
+            style: Style::new(),
+        }
+    }
+
+    /// style of the border and cluster headings
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// height needed to show every binding, including the border
+    pub fn height(&self) -> u16 {
+        let rows: usize = self
+            .keymap
+            .clusters()
+            .map(|(_, actions)| actions.len() + 1)
+            .sum();
+
+        rows as u16 + 2
+    }
+
+    fn rows(&self) -> Vec<Row<'a>> {
+        let heading = self.style.add_modifier(Modifier::BOLD);
+
+        self.keymap
+            .clusters()
+            .flat_map(|(cluster, actions)| {
+                let bindings = actions.iter().map(|bound| {
+                    let keys: Vec<_> = bound.set.bindings().map(format_binding).collect();
+                    Row::new([Cell::from(bound.label), Cell::from(keys.join(", "))])
+                });
+
+                [Row::new([Cell::from(cluster).style(heading)])]
+                    .into_iter()
+                    .chain(bindings)
+            })
+            .collect()
+    }
+}
+
+impl Widget for HelpWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Panel::new().title("help").style(self.style).block();
+
+        Clear.render(area, buf);
+        Table::new(self.rows(), [Constraint::Fill(1), Constraint::Fill(1)])
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyEvent;
+
+    use super::*;
+    use crate::control_cluster;
+
+    enum Zoom {
+        In,
+        Out,
+    }
+
+    control_cluster!(
+        Zoom,
+        "zoom",
+        (In, "in", "zoom in", ["=", "ctrl+up"]),
+        (Out, "out", "zoom out", ["-"])
+    );
+
+    #[test]
+    fn test_help() {
+        let keymap = Keymap::default().register::<Zoom>().unwrap();
+        let widget = HelpWidget::new(&keymap);
+        assert_eq!(widget.height(), 5);
+
+        let area = Rect::new(0, 0, 30, widget.height());
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+
+        let text: String = buf.content().iter().map(|cell| cell.symbol()).collect();
+        assert!(text.contains("zoom in"), "labels should be listed");
+        assert!(
+            text.contains("=, ctrl+up"),
+            "bindings should be listed in declaration order"
+        );
+    }
+}
@@ -1,5 +1,129 @@
+use std::{fs, path::Path};
+
+use cog_core::{
+    capabilities::Capabilities,
+    theme::{self, okabe_ito, ColorDepth, Theme, ThemeFile, Themes},
+};
+use eyre::{Result, WrapErr};
 use ratatui::style::Color;
 
-pub const PRIMARY: Color = Color::LightBlue;
-pub const SECONDARY: Color = Color::DarkGray;
-pub const ACCENT: Color = Color::Blue;
+pub fn primary() -> Color {
+    theme::color("primary")
+}
+
+pub fn secondary() -> Color {
+    theme::color("secondary")
+}
+
+pub fn accent() -> Color {
+    theme::color("accent")
+}
+
+fn default() -> Theme {
+    Theme::new()
+        .color("primary", Color::LightBlue)
+        .color("secondary", Color::DarkGray)
+        .color("accent", Color::Blue)
+        .color("item.iron", Color::DarkGray)
+        .color("item.copper", Color::Yellow)
+        .color("item.gold", Color::LightYellow)
+        .color("item.silver", Color::Gray)
+        .color("item.tin", Color::LightBlue)
+        .color("item.pod", Color::DarkGray)
+        .color("item.tunnel", Color::White)
+        .color("item.pusher", Color::White)
+        .color("item.processor", Color::Magenta)
+}
+
+/// ores drawn from the okabe-ito palette so they stay apart with red-green color blindness
+fn red_green() -> Theme {
+    Theme::new()
+        .color("primary", okabe_ito::SKY_BLUE)
+        .color("accent", okabe_ito::BLUE)
+        .color("item.iron", Color::DarkGray)
+        .color("item.copper", okabe_ito::VERMILLION)
+        .color("item.gold", okabe_ito::YELLOW)
+        .color("item.silver", Color::Gray)
+        .color("item.tin", okabe_ito::SKY_BLUE)
+        .color("item.processor", okabe_ito::REDDISH_PURPLE)
+}
+
+/// avoids pairing blues with greens and yellows, which blue-yellow color blindness merges
+fn tritanopia() -> Theme {
+    Theme::new()
+        .color("primary", okabe_ito::BLUISH_GREEN)
+        .color("accent", okabe_ito::VERMILLION)
+        .color("item.iron", Color::DarkGray)
+        .color("item.copper", okabe_ito::VERMILLION)
+        .color("item.gold", okabe_ito::REDDISH_PURPLE)
+        .color("item.silver", Color::Gray)
+        .color("item.tin", okabe_ito::BLUISH_GREEN)
+        .color("item.processor", okabe_ito::ORANGE)
+}
+
+/// the built-in themes, starting on the default
+pub fn builtin(depth: ColorDepth) -> Themes {
+    Themes::new(depth)
+        .add("default", default())
+        .add("protanopia", red_green())
+        .add("deuteranopia", red_green())
+        .add("tritanopia", tritanopia())
+}
+
+/// builds the built-in themes for the detected color depth, extended by the toml file at `path` if it exists
+///
+/// a file looks like
+/// ```toml
+/// theme = "mine"
+/// # one of mono, ansi16, ansi256 or truecolor, detected from the terminal by default
+/// depth = "ansi16"
+///
+/// [themes.mine]
+/// accent = "#e69f00"
+/// "item.gold" = "light-yellow"
+/// ```
+pub fn load_themes(path: impl AsRef<Path>) -> Result<Themes> {
+    let path = path.as_ref();
+    let themes = builtin(Capabilities::get().color);
+
+    if !path.exists() {
+        return Ok(themes);
+    }
+
+    let file: ThemeFile = toml::from_str(&fs::read_to_string(path)?)
+        .wrap_err_with(|| format!("could not parse {}", path.display()))?;
+    themes
+        .apply(file)
+        .wrap_err_with(|| format!("invalid theme in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_load_themes() {
+        let themes = load_themes("missing.toml").unwrap();
+        assert_eq!(themes.current(), Some("default"));
+
+        let path = env::temp_dir().join(format!("cog_test_theme_{}.toml", process::id()));
+        fs::write(
+            &path,
+            "theme = \"mine\"\ndepth = \"truecolor\"\n[themes.mine]\naccent = \"#e69f00\"\n",
+        )
+        .unwrap();
+        let themes = load_themes(&path).unwrap();
+        assert_eq!(themes.get("accent"), okabe_ito::ORANGE);
+        assert_eq!(
+            themes.get("item.processor"),
+            Color::Magenta,
+            "colors missing from a theme should come from the default"
+        );
+
+        fs::write(&path, "theme = \"nope\"\n").unwrap();
+        assert!(load_themes(&path).is_err(), "unknown themes should error");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 pub mod entity;
 pub mod inventory;
+pub mod status;
 pub mod store;
 pub mod world;
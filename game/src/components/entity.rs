@@ -2,12 +2,14 @@ use tunnel::tunnel_tick;
 use hecs::{Entity, Query, World};
 use player::PlayerData;
 use pod::pod_tick;
+use processor::processor_tick;
 
 use super::store::Store;
 
 pub mod tunnel;
 pub mod player;
 pub mod pod;
+pub mod processor;
 
 pub fn get_player<Q: Query>(entities: &mut World) -> Option<(Entity, <Q as Query>::Item<'_>)> {
     entities
@@ -20,4 +22,5 @@ pub fn get_player<Q: Query>(entities: &mut World) -> Option<(Entity, <Q as Query
 pub fn tick(store: &mut Store) {
     pod_tick(store);
     tunnel_tick(store);
+    processor_tick(store);
 }
@@ -1,11 +1,12 @@
 use hecs::EntityBuilder;
+use serde::{Deserialize, Serialize};
 
 use crate::components::{
     inventory::{player::PlayerInventory, Inventory},
     world::Position,
 };
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct PlayerData;
 
 pub fn player_builder(position: Position) -> EntityBuilder {
@@ -1,4 +1,5 @@
 use hecs::EntityBuilder;
+use serde::{Deserialize, Serialize};
 
 use crate::components::{
     inventory::{simple::SimpleInventory, Inventory, PrepareOperation},
@@ -6,6 +7,7 @@ use crate::components::{
     world::{items::Item, Position},
 };
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PodData(pub Item);
 
 pub fn pod_builder(resource: Item, position: Position) -> EntityBuilder {
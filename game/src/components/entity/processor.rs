@@ -0,0 +1,467 @@
+use std::mem;
+
+use eyre::{Result, eyre};
+use hecs::{Entity, EntityBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    inventory::{Amount, Inventory, PrepareOperation},
+    store::Store,
+    world::{Direction, Position},
+};
+
+pub type Value = i64;
+pub type Address = usize;
+
+pub const REGISTERS: usize = 8;
+pub const INSTRUCTIONS_PER_TICK: usize = 16;
+
+/// one of a processor's registers, checked when created or loaded so instructions can't refer past them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "usize", into = "usize")]
+pub struct Register(usize);
+
+impl Register {
+    pub fn new(index: usize) -> Result<Self> {
+        if index >= REGISTERS {
+            return Err(eyre!("register r{} out of range", index));
+        }
+
+        Ok(Self(index))
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl TryFrom<usize> for Register {
+    type Error = eyre::Report;
+
+    fn try_from(index: usize) -> Result<Self> {
+        Self::new(index)
+    }
+}
+
+impl From<Register> for usize {
+    fn from(register: Register) -> Self {
+        register.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Instruction {
+    /// r = value
+    Set(Register, Value),
+    /// a = b
+    Copy(Register, Register),
+    /// a = a + b
+    Add(Register, Register),
+    /// a = a - b
+    Sub(Register, Register),
+    Jump(Address),
+    JumpZero(Register, Address),
+    JumpNotZero(Register, Address),
+    /// r = amount of items held by the neighbour in direction
+    Count(Register, Direction),
+    /// moves up to r items from one neighbour to another, r = amount moved
+    Move(Direction, Direction, Register),
+    /// ends execution for the current tick
+    Yield,
+    Halt,
+}
+
+/// access to the inventories surrounding a processor
+pub trait Neighbours {
+    fn count(&mut self, direction: Direction) -> Option<Amount>;
+    fn transfer(&mut self, from: Direction, to: Direction, amount: Amount) -> Option<Amount>;
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ProcessorData {
+    pub program: Vec<Instruction>,
+    pub registers: [Value; REGISTERS],
+    pub counter: Address,
+    pub halted: bool,
+}
+
+impl ProcessorData {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            program,
+            ..Default::default()
+        }
+    }
+
+    /// executes a single instruction, returning false when execution should stop for this tick
+    pub fn step(&mut self, neighbours: &mut impl Neighbours) -> bool {
+        if self.halted {
+            return false;
+        }
+
+        let Some(&instruction) = self.program.get(self.counter) else {
+            self.halted = true;
+            return false;
+        };
+        self.counter += 1;
+
+        let r = &mut self.registers;
+        match instruction {
+            Instruction::Set(a, value) => r[a.0] = value,
+            Instruction::Copy(a, b) => r[a.0] = r[b.0],
+            Instruction::Add(a, b) => r[a.0] = r[a.0].wrapping_add(r[b.0]),
+            Instruction::Sub(a, b) => r[a.0] = r[a.0].wrapping_sub(r[b.0]),
+            Instruction::Jump(address) => self.counter = address,
+            Instruction::JumpZero(a, address) => {
+                if r[a.0] == 0 {
+                    self.counter = address;
+                }
+            }
+            Instruction::JumpNotZero(a, address) => {
+                if r[a.0] != 0 {
+                    self.counter = address;
+                }
+            }
+            Instruction::Count(a, direction) => {
+                r[a.0] = neighbours.count(direction).unwrap_or(0) as Value;
+            }
+            Instruction::Move(from, to, a) => {
+                let amount = r[a.0].max(0) as Amount;
+                r[a.0] = neighbours.transfer(from, to, amount).unwrap_or(0) as Value;
+            }
+            Instruction::Yield => return false,
+            Instruction::Halt => {
+                self.halted = true;
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn run(&mut self, neighbours: &mut impl Neighbours, budget: usize) {
+        for _ in 0..budget {
+            if !self.step(neighbours) {
+                break;
+            }
+        }
+    }
+}
+
+fn register(token: &str) -> Result<Register> {
+    let index = token
+        .strip_prefix('r')
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| eyre!("expected register, found {:?}", token))?;
+
+    Register::new(index)
+}
+
+fn direction(token: &str) -> Result<Direction> {
+    Ok(match token {
+        "north" => Direction::North,
+        "south" => Direction::South,
+        "east" => Direction::East,
+        "west" => Direction::West,
+        _ => return Err(eyre!("expected direction, found {:?}", token)),
+    })
+}
+
+fn number<T: std::str::FromStr>(token: &str) -> Result<T> {
+    token
+        .parse()
+        .map_err(|_| eyre!("expected number, found {:?}", token))
+}
+
+fn instruction(line: &str) -> Result<Instruction> {
+    let tokens: Vec<_> = line.split_whitespace().collect();
+
+    Ok(match tokens.as_slice() {
+        ["set", a, v] => Instruction::Set(register(a)?, number(v)?),
+        ["copy", a, b] => Instruction::Copy(register(a)?, register(b)?),
+        ["add", a, b] => Instruction::Add(register(a)?, register(b)?),
+        ["sub", a, b] => Instruction::Sub(register(a)?, register(b)?),
+        ["jump", address] => Instruction::Jump(number(address)?),
+        ["jz", a, address] => Instruction::JumpZero(register(a)?, number(address)?),
+        ["jnz", a, address] => Instruction::JumpNotZero(register(a)?, number(address)?),
+        ["count", a, d] => Instruction::Count(register(a)?, direction(d)?),
+        ["move", from, to, a] => Instruction::Move(direction(from)?, direction(to)?, register(a)?),
+        ["yield"] => Instruction::Yield,
+        ["halt"] => Instruction::Halt,
+        _ => return Err(eyre!("unknown instruction {:?}", line)),
+    })
+}
+
+/// assembles a program, one instruction per line with `#` starting a comment
+pub fn parse_program(source: &str) -> Result<Vec<Instruction>> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i, line.split('#').next().unwrap_or_default().trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| instruction(line).map_err(|err| eyre!("line {}: {}", i + 1, err)))
+        .collect()
+}
+
+pub fn processor_builder(program: Vec<Instruction>, position: Position) -> EntityBuilder {
+    let mut builder = EntityBuilder::new();
+
+    builder.add(ProcessorData::new(program)).add(position);
+
+    builder
+}
+
+struct StoreNeighbours<'a> {
+    store: &'a mut Store,
+    position: Position,
+}
+
+impl StoreNeighbours<'_> {
+    fn entity(&self, direction: Direction) -> Option<Entity> {
+        let position = self.position.move_by(direction, 1)?;
+        self.store.world.grid[position].entity().copied()
+    }
+}
+
+impl Neighbours for StoreNeighbours<'_> {
+    fn count(&mut self, direction: Direction) -> Option<Amount> {
+        let entity = self.entity(direction)?;
+        let inventory = self
+            .store
+            .entities
+            .query_one_mut::<&Box<dyn Inventory>>(entity)
+            .ok()?;
+
+        Some(inventory.slots().iter().map(|(_, amount)| amount).sum())
+    }
+
+    fn transfer(&mut self, from: Direction, to: Direction, amount: Amount) -> Option<Amount> {
+        let (from, to) = (self.entity(from)?, self.entity(to)?);
+        if from == to {
+            return None;
+        }
+
+        let [from, to] = self
+            .store
+            .entities
+            .query_many_mut::<&mut Box<dyn Inventory>, 2>([from, to]);
+        let (from, to) = (from.ok()?, to.ok()?);
+
+        let &(item, available) = *from.slots().iter().find(|(_, amount)| *amount > 0)?;
+        let amount = amount.min(available);
+        if amount < 1 {
+            return None;
+        }
+
+        let (from_op, ..) = from.prepare(PrepareOperation::Remove(Some(item), Some(amount)))?;
+        let (to_op, ..) = to.prepare(PrepareOperation::Add(item, amount))?;
+
+        from.modify(from_op);
+        to.modify(to_op);
+
+        Some(amount)
+    }
+}
+
+pub fn processor_tick(store: &mut Store) {
+    let processors: Vec<_> = store
+        .entities
+        .query_mut::<(&ProcessorData, &Position)>()
+        .into_iter()
+        .map(|(entity, (_, &position))| (entity, position))
+        .collect();
+
+    for (entity, position) in processors {
+        // taken out of the world so neighbouring inventories can be borrowed while running
+        let mut data = match store.entities.query_one_mut::<&mut ProcessorData>(entity) {
+            Ok(data) => mem::take(data),
+            Err(_) => continue,
+        };
+
+        data.run(
+            &mut StoreNeighbours { store, position },
+            INSTRUCTIONS_PER_TICK,
+        );
+
+        if let Ok(slot) = store.entities.query_one_mut::<&mut ProcessorData>(entity) {
+            *slot = data;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{entity::pod::pod_builder, world::items::Item};
+
+    #[derive(Default)]
+    struct MockNeighbours {
+        count: Amount,
+        moved: Amount,
+    }
+
+    impl Neighbours for MockNeighbours {
+        fn count(&mut self, _: Direction) -> Option<Amount> {
+            Some(self.count)
+        }
+
+        fn transfer(&mut self, _: Direction, _: Direction, amount: Amount) -> Option<Amount> {
+            let amount = amount.min(self.count);
+            self.count -= amount;
+            self.moved += amount;
+            Some(amount)
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_program("set r0 5 # comment\n\nmove north south r0\nhalt").unwrap(),
+            vec![
+                Instruction::Set(Register(0), 5),
+                Instruction::Move(Direction::North, Direction::South, Register(0)),
+                Instruction::Halt,
+            ]
+        );
+
+        assert!(
+            parse_program("set r8 1").is_err(),
+            "out of range register should not parse"
+        );
+        assert!(
+            parse_program("explode").is_err(),
+            "unknown instruction should not parse"
+        );
+    }
+
+    #[test]
+    fn test_load_register() {
+        let program = bincode::serialize(&vec![Instruction::Set(Register(7), 1)]).unwrap();
+        assert_eq!(
+            bincode::deserialize::<Vec<Instruction>>(&program).unwrap(),
+            [Instruction::Set(Register(7), 1)]
+        );
+
+        let program = bincode::serialize(&vec![(0u32, REGISTERS, 1 as Value)]).unwrap();
+        assert!(
+            bincode::deserialize::<Vec<Instruction>>(&program).is_err(),
+            "out of range register should not load"
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        // infinite loop should be bounded by the budget
+        let mut data = ProcessorData::new(vec![
+            Instruction::Set(Register(1), 1),
+            Instruction::Add(Register(0), Register(1)),
+            Instruction::Jump(1),
+        ]);
+        data.run(&mut MockNeighbours::default(), 7);
+
+        assert_eq!(
+            data.registers[0], 3,
+            "budget should limit executed instructions"
+        );
+        assert!(!data.halted, "looping program should not halt");
+    }
+
+    #[test]
+    fn test_neighbours() {
+        // move everything counted until the neighbour is empty
+        let program = parse_program(
+            "count r0 north
+            jz r0 4
+            move north south r0
+            jump 0
+            halt",
+        )
+        .unwrap();
+
+        let mut neighbours = MockNeighbours {
+            count: 5,
+            ..Default::default()
+        };
+        let mut data = ProcessorData::new(program);
+        data.run(&mut neighbours, INSTRUCTIONS_PER_TICK);
+
+        assert_eq!(neighbours.moved, 5, "all items should be moved");
+        assert!(data.halted, "program should halt once empty");
+    }
+
+    #[test]
+    fn test_tick() {
+        let mut store = Store::new(44);
+        let mut place = |mut builder: EntityBuilder, item: fn(Entity) -> Item, position| {
+            let entity = store.entities.spawn(builder.build());
+            store.world.place(item(entity), position);
+            entity
+        };
+
+        // on the top edge, so north is off the grid
+        let program = parse_program(
+            "set r2 1
+            move south south r2
+            count r0 south
+            move south west r0
+            count r1 north
+            halt",
+        )
+        .unwrap();
+        let processor = place(
+            processor_builder(program, Position(0, 10)),
+            Item::Processor,
+            Position(0, 10),
+        );
+        let source = place(
+            pod_builder(Item::RawIron, Position(1, 10)),
+            Item::Pod,
+            Position(1, 10),
+        );
+        let destination = place(
+            pod_builder(Item::RawIron, Position(0, 11)),
+            Item::Pod,
+            Position(0, 11),
+        );
+
+        let inventory = store
+            .entities
+            .query_one_mut::<&mut Box<dyn Inventory>>(source)
+            .unwrap();
+        let (op, ..) = inventory
+            .prepare(PrepareOperation::Add(Item::RawIron, 5))
+            .unwrap();
+        inventory.modify(op);
+
+        processor_tick(&mut store);
+
+        let mut count = |entity| {
+            let inventory = store
+                .entities
+                .query_one_mut::<&Box<dyn Inventory>>(entity)
+                .unwrap();
+            inventory
+                .slots()
+                .iter()
+                .map(|(_, amount)| amount)
+                .sum::<Amount>()
+        };
+        assert_eq!(count(source), 0, "items should be taken from the source");
+        assert_eq!(count(destination), 5, "items should reach the destination");
+
+        let data = store
+            .entities
+            .query_one_mut::<&ProcessorData>(processor)
+            .unwrap();
+        assert!(data.halted, "program should run within the tick");
+        assert_eq!(data.registers[0], 5, "amount moved should be stored");
+        assert_eq!(
+            data.registers[1], 0,
+            "neighbours off the grid should be empty"
+        );
+        assert_eq!(
+            data.registers[2], 0,
+            "moving from a neighbour to itself should do nothing"
+        );
+    }
+}
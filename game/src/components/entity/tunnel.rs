@@ -1,6 +1,7 @@
 use std::collections::{HashMap, VecDeque};
 
 use hecs::{Entity, EntityBuilder};
+use serde::{Deserialize, Serialize};
 use topological_sort::TopologicalSort;
 
 use crate::components::{
@@ -9,6 +10,7 @@ use crate::components::{
     world::{Direction, Position},
 };
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TunnelData;
 
 pub fn tunnel_builder(direction: Direction, position: Position) -> EntityBuilder {
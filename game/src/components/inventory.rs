@@ -1,12 +1,29 @@
-use std::fmt;
+use std::{fmt, rc::Rc};
 
+use cog_core::{
+    component::Component,
+    runtime::RuntimeMessage,
+    theme,
+    ui::Panel,
+    util::{controls::ControlCluster, hit_test_any},
+    AppMessage, Model,
+};
+use crossterm::event::{Event, MouseButton, MouseEvent, MouseEventKind};
+use eyre::{eyre, Result};
+use player::PlayerInventory;
 use ratatui::{
     layout,
     prelude::{Buffer, Rect},
-    widgets,
+    style::{Modifier, Style},
+    widgets::{self, Widget},
+    Frame,
 };
+use serde::{Deserialize, Serialize};
+use simple::SimpleInventory;
+
+use crate::{colors, controls::BasicCluster};
 
-use super::world::items::Item;
+use super::{entity::get_player, store::RRStore, world::items::Item};
 
 pub mod simple;
 pub mod player;
@@ -35,6 +52,13 @@ pub trait Inventory: Send + Sync {
     fn prepare(&self, operation: PrepareOperation) -> Option<(ModifyOperation, Before, After)>;
     /// warning: the inventory is expected not to change between transaction verification and modification
     fn modify(&mut self, operation: ModifyOperation);
+    fn save(&self) -> SavedInventory;
+
+    /// slot to prefer when adding or removing items, for inventories that support it
+    fn preferred(&self) -> Option<Slot> {
+        None
+    }
+    fn set_preferred(&mut self, _slot: Slot) {}
 
     fn swap(&mut self, other: &mut Box<dyn Inventory>, operation: PrepareOperation) -> Option<()> {
         let (self_op, before, _) = self.prepare(operation)?;
@@ -47,46 +71,313 @@ pub trait Inventory: Send + Sync {
     }
 }
 
+/// serializable form of every inventory implementation
+#[derive(Serialize, Deserialize)]
+pub enum SavedInventory {
+    Simple {
+        limit: usize,
+        slots: Vec<(Item, Amount)>,
+    },
+    Player {
+        slots: Vec<(Item, Amount)>,
+        preferred: Slot,
+        item_limit: Amount,
+    },
+}
+
+impl SavedInventory {
+    /// rebuilds the inventory, failing if the save couldn't have come from one
+    pub fn load(self) -> Result<Box<dyn Inventory>> {
+        Ok(match self {
+            SavedInventory::Simple { limit, slots } => {
+                let mut inventory = SimpleInventory::new(limit);
+                // slots are keyed by item, so they are rebuilt rather than restored
+                for (item, amount) in slots {
+                    if let Some((op, ..)) = inventory.prepare(PrepareOperation::Add(item, amount)) {
+                        inventory.modify(op);
+                    }
+                }
+
+                Box::new(inventory)
+            }
+            SavedInventory::Player {
+                slots,
+                preferred,
+                item_limit,
+            } => {
+                if slots.is_empty() {
+                    return Err(eyre!("save has a player inventory without slots"));
+                }
+                if preferred as usize >= slots.len() {
+                    return Err(eyre!(
+                        "save prefers slot {} of a player inventory with {}",
+                        preferred,
+                        slots.len()
+                    ));
+                }
+
+                let mut inventory = PlayerInventory::new(slots.len(), item_limit);
+                inventory.slots = slots.into_boxed_slice();
+                inventory.preferred = preferred;
+
+                Box::new(inventory)
+            }
+        })
+    }
+
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        match self {
+            SavedInventory::Simple { slots, .. } => slots.iter_mut(),
+            SavedInventory::Player { slots, .. } => slots.iter_mut(),
+        }
+        .map(|(item, _)| item)
+    }
+}
+
 impl fmt::Debug for dyn Inventory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self.slots())
     }
 }
 
-pub struct InventoryWidget<'a>(&'a dyn Inventory);
+pub struct InventoryWidget<'a> {
+    inventory: &'a dyn Inventory,
+    focused: bool,
+}
 
 impl<'a> InventoryWidget<'a> {
     pub fn new(inventory: &'a dyn Inventory) -> Self {
-        Self(inventory)
+        Self {
+            inventory,
+            focused: false,
+        }
+    }
+
+    /// highlights the border to show the inventory receives key presses
+    pub fn focused(mut self, focused: bool) -> Self {
+        self.focused = focused;
+        self
+    }
+
+    /// areas each slot is rendered in, in the same order as `Inventory::slots`
+    pub fn slot_areas(&self, area: Rect) -> Rc<[Rect]> {
+        let slots = self.inventory.slots().len();
+
+        layout::Layout::default()
+            .direction(layout::Direction::Horizontal)
+            .constraints(vec![layout::Constraint::Ratio(1, slots as u32); slots])
+            .split(self.panel().inner(area))
+    }
+
+    fn panel(&self) -> Panel {
+        let style = if self.focused {
+            theme::emphasis(colors::accent())
+        } else {
+            Style::new()
+        };
+
+        Panel::new().style(style).focused(self.focused)
     }
 }
 
-impl widgets::Widget for InventoryWidget<'_> {
+impl Widget for InventoryWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized,
     {
-        let slots = self.0.slots();
-
-        let block = widgets::Block::bordered().border_type(widgets::BorderType::Rounded);
-        let block_area = block.inner(area);
+        let slots = self.inventory.slots();
+        let layout_slots = self.slot_areas(area);
 
-        widgets::Clear.render(area, buf);
-        block.render(area, buf);
-
-        let layout_slots = layout::Layout::default()
-            .direction(layout::Direction::Horizontal)
-            .constraints(vec![
-                layout::Constraint::Ratio(1, slots.len() as u32);
-                slots.len()
-            ])
-            .split(block_area);
+        (&self.panel()).render(area, buf);
 
+        let preferred = self.inventory.preferred();
         for (i, (item, amount)) in slots.into_iter().enumerate() {
+            let mut style = Style::new().fg(item.color());
+            if preferred == Some(i as Slot) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+
             widgets::Paragraph::new(format!("{}\n{}", item, amount))
                 .alignment(ratatui::layout::Alignment::Center)
-                .style(item.color())
+                .style(style)
                 .render(layout_slots[i], buf);
         }
     }
 }
+
+#[derive(Debug)]
+pub enum InventoryMessage {}
+
+/// the player's inventory, docked to the bottom of the screen
+pub struct InventoryModel {
+    store: RRStore,
+    focused: bool,
+    slots: Rc<[Rect]>,
+}
+
+impl InventoryModel {
+    pub fn new(store: RRStore) -> Self {
+        Self {
+            store,
+            focused: false,
+            slots: Rc::new([]),
+        }
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    fn with_inventory<R>(&self, f: impl FnOnce(&mut Box<dyn Inventory>) -> R) -> R {
+        let mut store = self.store.borrow_mut();
+        let (_, inventory) = get_player::<&mut Box<dyn Inventory>>(&mut store.entities)
+            .expect("player should exist");
+        f(inventory)
+    }
+
+    /// moves the preferred slot by `offset`, wrapping around
+    fn shift_preferred(&self, offset: i64) {
+        self.with_inventory(|inventory| {
+            let slots = inventory.slots().len() as i64;
+            if let Some(preferred) = inventory.preferred() {
+                inventory.set_preferred((preferred as i64 + offset).rem_euclid(slots) as Slot);
+            }
+        })
+    }
+}
+
+impl Model<InventoryMessage> for InventoryModel {
+    fn view(&mut self, frame: &mut Frame) {
+        let mut store = self.store.borrow_mut();
+        let (_, inventory) =
+            get_player::<&Box<dyn Inventory>>(&mut store.entities).expect("player should exist");
+
+        let height = 4;
+        let area = frame.area();
+        let inventory_area = Rect::new(
+            0,
+            area.height - height,
+            inventory.slots().len() as u16 * (height as f32 * 2.5) as u16,
+            height,
+        )
+        .clamp(area);
+
+        let widget = InventoryWidget::new(inventory.as_ref()).focused(self.focused);
+        self.slots = widget.slot_areas(inventory_area);
+        widget.render(inventory_area, frame.buffer_mut());
+    }
+
+    fn update(
+        &mut self,
+        message: AppMessage<InventoryMessage>,
+    ) -> RuntimeMessage<InventoryMessage> {
+        match message {
+            AppMessage::Event(Event::Key(event)) => match BasicCluster::contains(&event) {
+                Some(BasicCluster::Left) => self.shift_preferred(-1),
+                Some(BasicCluster::Right) => self.shift_preferred(1),
+                _ => (),
+            },
+            AppMessage::Event(Event::Mouse(event)) => {
+                if let Some((slot, _)) = hit_test_any(&self.slots, &event) {
+                    self.with_inventory(|inventory| inventory.set_preferred(slot as Slot));
+                }
+            }
+            _ => (),
+        }
+
+        RuntimeMessage::Empty
+    }
+}
+
+impl Component<InventoryMessage> for InventoryModel {
+    fn event(&mut self, event: &Event) -> Option<RuntimeMessage<InventoryMessage>> {
+        let handled = match event {
+            Event::Key(event) => matches!(
+                BasicCluster::contains(event),
+                Some(BasicCluster::Left | BasicCluster::Right)
+            ),
+            Event::Mouse(
+                event @ MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    ..
+                },
+            ) => hit_test_any(&self.slots, event).is_some(),
+            _ => false,
+        };
+
+        handled.then(|| self.update(AppMessage::Event(event.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use cog_core::{
+        runtime::headless::headless,
+        testing::{assert_snapshot, render_widget},
+        theme::ColorDepth,
+    };
+    use crossterm::event::{KeyCode, KeyEvent};
+    use futures::executor::block_on;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::{colors, components::store::Store};
+
+    #[test]
+    fn test_shift_preferred() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        let mut model = InventoryModel::new(store.clone());
+        let preferred = || {
+            let mut store = store.borrow_mut();
+            let (_, inventory) = get_player::<&Box<dyn Inventory>>(&mut store.entities)
+                .expect("player should exist");
+            (inventory.preferred(), inventory.slots().len() as Slot)
+        };
+
+        block_on(headless(
+            &mut model,
+            [Event::Key(KeyEvent::from(KeyCode::Char('h')))],
+            TestBackend::new(40, 10),
+        ))
+        .expect("headless run should succeed");
+
+        let (slot, slots) = preferred();
+        assert_eq!(
+            slot,
+            Some(slots - 1),
+            "moving left from the first slot should wrap"
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let player = |slots: Vec<(Item, Amount)>, preferred| SavedInventory::Player {
+            slots,
+            preferred,
+            item_limit: 10,
+        };
+
+        assert!(
+            player(vec![], 0).load().is_err(),
+            "player inventories without slots should not load"
+        );
+        assert!(
+            player(vec![(Item::Empty, 0); 2], 2).load().is_err(),
+            "preferring a slot past the end should not load"
+        );
+        assert!(player(vec![(Item::Empty, 0); 2], 1).load().is_ok());
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let _themes = colors::builtin(ColorDepth::TrueColor).install_local();
+        let mut store = Store::new(44);
+        let (_, inventory) =
+            get_player::<&Box<dyn Inventory>>(&mut store.entities).expect("player should exist");
+
+        let widget = InventoryWidget::new(inventory.as_ref()).focused(true);
+        assert_snapshot("inventory", &render_widget(widget, 50, 4));
+    }
+}
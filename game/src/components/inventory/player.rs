@@ -1,7 +1,9 @@
 use crate::components::world::items::Item;
 use std::iter::{once, repeat_n};
 
-use super::{After, Amount, Before, Inventory, ModifyOperation, PrepareOperation, Slot};
+use super::{
+    After, Amount, Before, Inventory, ModifyOperation, PrepareOperation, SavedInventory, Slot,
+};
 
 pub struct PlayerInventory {
     pub slots: Box<[(Item, Amount)]>,
@@ -91,6 +93,24 @@ impl Inventory for PlayerInventory {
         }
         *slot = (operation.item, operation.amount);
     }
+
+    fn preferred(&self) -> Option<Slot> {
+        Some(self.preferred)
+    }
+
+    fn set_preferred(&mut self, slot: Slot) {
+        if (slot as usize) < self.slots.len() {
+            self.preferred = slot;
+        }
+    }
+
+    fn save(&self) -> SavedInventory {
+        SavedInventory::Player {
+            slots: self.slots.to_vec(),
+            preferred: self.preferred,
+            item_limit: self.item_limit,
+        }
+    }
 }
 
 #[cfg(test)]
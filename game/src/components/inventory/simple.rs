@@ -5,7 +5,9 @@ use std::{
 
 use crate::components::world::items::Item;
 
-use super::{After, Amount, Before, Inventory, ModifyOperation, PrepareOperation, Slot};
+use super::{
+    After, Amount, Before, Inventory, ModifyOperation, PrepareOperation, SavedInventory, Slot,
+};
 
 fn hash(v: impl Hash) -> u64 {
     let mut hasher = DefaultHasher::new();
@@ -89,6 +91,13 @@ impl Inventory for SimpleInventory {
             self.slots.remove(&operation.slot);
         }
     }
+
+    fn save(&self) -> SavedInventory {
+        SavedInventory::Simple {
+            limit: self.limit,
+            slots: self.slots.values().copied().collect(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,189 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use cog_core::{
+    runtime::{
+        subscription::{Subscription, SubscriptionId},
+        RuntimeMessage,
+    },
+    ui::Panel,
+    util::controls::ControlCluster,
+    AppMessage, Model,
+};
+use crossterm::event::Event;
+use ratatui::{
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::{colors, controls::ActionCluster};
+
+use super::{
+    entity::{get_player, tick},
+    inventory::Inventory,
+    store::RRStore,
+    world::items::Item,
+};
+
+const TICK: SubscriptionId = "status_tick";
+
+/// lines the status readout takes up, including its border
+pub const HEIGHT: u16 = 4;
+
+#[derive(Debug)]
+pub enum StatusMessage {
+    Tick,
+}
+
+/// a compact readout of the factory, meant for an inline viewport
+///
+/// the factory keeps running from the save while it's shown, as a preview of where it's heading,
+/// but that progress is never saved, so the next session starts from the save again
+pub struct StatusModel {
+    store: RRStore,
+    ticks: u64,
+}
+
+impl StatusModel {
+    pub fn new(store: RRStore) -> Self {
+        Self { store, ticks: 0 }
+    }
+
+    /// labelled counts, such as "12 Tunnel", separated by dots
+    fn counts<'a>(label: &'a str, counts: BTreeMap<String, u64>) -> Line<'a> {
+        let counts = counts
+            .into_iter()
+            .map(|(name, count)| format!("{} {}", count, name))
+            .collect::<Vec<_>>()
+            .join(" · ");
+
+        Line::from(vec![
+            Span::styled(
+                format!("{:<10}", label),
+                Style::new().fg(colors::primary()).bold(),
+            ),
+            Span::raw(counts),
+        ])
+    }
+}
+
+impl Model<StatusMessage> for StatusModel {
+    fn update(&mut self, message: AppMessage<StatusMessage>) -> RuntimeMessage<StatusMessage> {
+        match message {
+            AppMessage::Init => RuntimeMessage::Subscribe(
+                TICK,
+                Subscription::interval(Duration::from_secs(1), || StatusMessage::Tick),
+            ),
+            AppMessage::App(StatusMessage::Tick) => {
+                tick(&mut self.store.borrow_mut());
+                self.ticks += 1;
+                RuntimeMessage::Empty
+            }
+            AppMessage::Event(Event::Key(key)) => match ActionCluster::contains(&key) {
+                Some(ActionCluster::Exit | ActionCluster::Back) => RuntimeMessage::Exit,
+                _ => RuntimeMessage::Empty,
+            },
+            _ => RuntimeMessage::Empty,
+        }
+    }
+
+    fn view(&mut self, frame: &mut Frame) {
+        let mut store = self.store.borrow_mut();
+
+        let mut placed = BTreeMap::new();
+        for item in store.world.grid.iter().filter(|item| **item != Item::Empty) {
+            *placed.entry(item.to_string()).or_default() += 1;
+        }
+
+        let mut held = BTreeMap::new();
+        let (_, inventory) =
+            get_player::<&Box<dyn Inventory>>(&mut store.entities).expect("player should exist");
+        for (item, amount) in inventory
+            .slots()
+            .iter()
+            .filter(|(item, _)| *item != Item::Empty)
+        {
+            *held.entry(item.to_string()).or_default() += amount;
+        }
+
+        let panel = Panel::new()
+            .title(format!("factory preview · tick {}", self.ticks))
+            .style(Style::new().fg(colors::accent()));
+        panel.render_with(
+            frame.area(),
+            frame.buffer_mut(),
+            Paragraph::new(vec![
+                Self::counts("placed", placed),
+                Self::counts("inventory", held),
+            ]),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use cog_core::runtime::headless::{headless, headless_timed};
+    use futures::executor::block_on;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::components::store::Store;
+
+    #[test]
+    fn test_status() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        let tunnels = store
+            .borrow()
+            .world
+            .grid
+            .iter()
+            .filter(|item| matches!(item, Item::Tunnel(_)))
+            .count();
+
+        let mut model = StatusModel::new(store);
+        let buffers = block_on(headless(
+            &mut model,
+            [Event::FocusGained],
+            TestBackend::new(120, HEIGHT),
+        ))
+        .expect("headless run should succeed");
+
+        let text: String = buffers[0]
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(
+            text.contains(&format!("{} Tunnel", tunnels)),
+            "placed tunnels should be counted: {}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_ticks() {
+        let mut model = StatusModel::new(Rc::new(RefCell::new(Store::new(44))));
+        let buffers = block_on(headless_timed(
+            &mut model,
+            [
+                (Duration::from_millis(500), Event::FocusGained),
+                (Duration::from_secs(3), Event::FocusGained),
+            ],
+            TestBackend::new(120, HEIGHT),
+        ))
+        .expect("headless run should succeed");
+
+        let titles = buffers
+            .iter()
+            .map(|buffer| {
+                let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+                text.contains(&format!("tick {}", model.ticks))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(model.ticks, 3, "the factory should tick once a second");
+        assert_eq!(titles, [false, true]);
+    }
+}
@@ -7,6 +7,8 @@ use crate::components::world::{World, items::Item};
 
 use super::entity::{player::player_builder, pod::pod_builder, tunnel::tunnel_builder};
 
+pub mod save;
+
 pub struct Store {
     pub rng: Xoshiro256PlusPlus,
     pub world: World,
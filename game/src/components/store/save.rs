@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use eyre::{Result, eyre};
+use hecs::{Entity, EntityBuilder};
+use ndarray::Array2;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{
+    entity::{player::PlayerData, pod::PodData, processor::ProcessorData, tunnel::TunnelData},
+    inventory::{Inventory, SavedInventory},
+    world::{Direction, Position, SIZE, World, items::Item},
+};
+
+use super::Store;
+
+/// bumped whenever the layout of `SaveData` changes
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct SavedEntity {
+    id: Entity,
+    pod: Option<PodData>,
+    tunnel: Option<TunnelData>,
+    processor: Option<ProcessorData>,
+    player: Option<PlayerData>,
+    direction: Option<Direction>,
+    position: Option<Position>,
+    inventory: Option<SavedInventory>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveData {
+    rng: Xoshiro256PlusPlus,
+    grid: Array2<Item>,
+    cursor: Position,
+    entities: Vec<SavedEntity>,
+}
+
+impl Store {
+    pub fn save(&self, writer: impl Write) -> Result<()> {
+        let entities = self
+            .entities
+            .iter()
+            .map(|entity| SavedEntity {
+                id: entity.entity(),
+                pod: entity.get::<&PodData>().map(|c| c.clone()),
+                tunnel: entity.get::<&TunnelData>().map(|c| c.clone()),
+                processor: entity.get::<&ProcessorData>().map(|c| c.clone()),
+                player: entity.get::<&PlayerData>().map(|c| c.clone()),
+                direction: entity.get::<&Direction>().map(|c| *c),
+                position: entity.get::<&Position>().map(|c| *c),
+                inventory: entity.get::<&Box<dyn Inventory>>().map(|c| c.save()),
+            })
+            .collect();
+
+        let data = SaveData {
+            rng: self.rng.clone(),
+            grid: self.world.grid.clone(),
+            cursor: self.world.cursor,
+            entities,
+        };
+
+        let mut writer = BufWriter::new(writer);
+        bincode::serialize_into(&mut writer, &VERSION)?;
+        bincode::serialize_into(&mut writer, &data)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn load(reader: impl Read) -> Result<Self> {
+        let mut reader = BufReader::new(reader);
+
+        let version: u32 = bincode::deserialize_from(&mut reader)?;
+        if version != VERSION {
+            return Err(eyre!(
+                "unsupported save version {} (expected {})",
+                version,
+                VERSION
+            ));
+        }
+        let mut data: SaveData = bincode::deserialize_from(&mut reader)?;
+
+        // everything indexes the grid by position, so a save from another world size can't be used
+        if data.grid.dim() != (SIZE, SIZE) {
+            let (rows, cols) = data.grid.dim();
+            return Err(eyre!(
+                "save grid is {}x{} (expected {}x{})",
+                rows,
+                cols,
+                SIZE,
+                SIZE
+            ));
+        }
+        let in_bounds = |Position(row, col): Position| row < SIZE && col < SIZE;
+        let positions = data.entities.iter().filter_map(|saved| saved.position);
+        if let Some(position) = [data.cursor]
+            .into_iter()
+            .chain(positions)
+            .find(|p| !in_bounds(*p))
+        {
+            return Err(eyre!("save has position {:?} outside the grid", position));
+        }
+
+        // entity ids are not stable across worlds, so every reference gets remapped
+        let mut entities = hecs::World::new();
+        let mut ids = HashMap::with_capacity(data.entities.len());
+        let mut inventories = Vec::new();
+
+        for saved in data.entities {
+            let mut builder = EntityBuilder::new();
+            saved.pod.map(|c| builder.add(c));
+            saved.tunnel.map(|c| builder.add(c));
+            saved.processor.map(|c| builder.add(c));
+            saved.player.map(|c| builder.add(c));
+            saved.direction.map(|c| builder.add(c));
+            saved.position.map(|c| builder.add(c));
+
+            let entity = entities.spawn(builder.build());
+            ids.insert(saved.id, entity);
+
+            if let Some(inventory) = saved.inventory {
+                inventories.push((entity, inventory));
+            }
+        }
+
+        let remap = |item: &mut Item| -> Result<()> {
+            *item = item
+                .remap(|e| ids.get(&e).copied())
+                .ok_or_else(|| eyre!("save references unknown entity in {:?}", item))?;
+            Ok(())
+        };
+
+        for (entity, mut inventory) in inventories {
+            inventory.items_mut().try_for_each(remap)?;
+            entities.insert_one(entity, inventory.load()?)?;
+        }
+        data.grid.iter_mut().try_for_each(remap)?;
+
+        Ok(Store {
+            rng: data.rng,
+            world: World {
+                grid: data.grid,
+                cursor: data.cursor,
+            },
+            entities,
+        })
+    }
+
+    /// saves beside `path` before replacing it, so a failed save leaves the previous one intact
+    pub fn save_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        let file = File::create(&temporary)?;
+        self.save(&file)?;
+        file.sync_all()?;
+        fs::rename(&temporary, path)?;
+
+        Ok(())
+    }
+
+    pub fn load_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load(File::open(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::components::entity::get_player;
+
+    #[test]
+    fn test_roundtrip() {
+        let mut store = Store::new(44);
+        let mut buffer = Vec::new();
+        store.save(&mut buffer).expect("store should save");
+
+        let mut loaded = Store::load(buffer.as_slice()).expect("store should load");
+
+        assert_eq!(
+            loaded.world.cursor, store.world.cursor,
+            "cursor should load"
+        );
+        assert_eq!(
+            loaded.rng.random::<u64>(),
+            store.rng.random::<u64>(),
+            "rng state should load"
+        );
+        assert_eq!(
+            loaded.entities.len(),
+            store.entities.len(),
+            "every entity should load"
+        );
+
+        // every entity referenced by the grid should exist after remapping
+        for item in loaded.world.grid.iter() {
+            if let Some(&entity) = item.entity() {
+                assert!(
+                    loaded.entities.contains(entity),
+                    "grid item {:?} should be remapped",
+                    item
+                );
+            }
+        }
+
+        let (_, position) =
+            get_player::<&Position>(&mut loaded.entities).expect("player should load");
+        assert_eq!(*position, store.world.cursor, "player position should load");
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut store = Store::new(44);
+        store.world.cursor = Position(SIZE, 0);
+        let mut buffer = Vec::new();
+        store.save(&mut buffer).unwrap();
+        assert!(
+            Store::load(buffer.as_slice()).is_err(),
+            "positions outside the grid should not load"
+        );
+
+        store.world.cursor = Position(0, 0);
+        store.world.grid = Array2::default((SIZE, SIZE + 1));
+        let mut buffer = Vec::new();
+        store.save(&mut buffer).unwrap();
+        assert!(
+            Store::load(buffer.as_slice()).is_err(),
+            "grids of another size should not load"
+        );
+    }
+
+    #[test]
+    fn test_save_file() {
+        let path = env::temp_dir().join(format!("cog_test_save_{}", process::id()));
+        let store = Store::new(44);
+        store.save_file(&path).expect("store should save");
+        store
+            .save_file(&path)
+            .expect("store should save over an existing save");
+
+        assert!(Store::load_file(&path).is_ok(), "saved file should load");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_version() {
+        let mut buffer = Vec::new();
+        bincode::serialize_into(&mut buffer, &(VERSION + 1)).unwrap();
+
+        assert!(
+            Store::load(buffer.as_slice()).is_err(),
+            "unknown save version should not load"
+        );
+    }
+}
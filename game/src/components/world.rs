@@ -1,25 +1,45 @@
-use std::iter::repeat_n;
-
-use cog_core::{AppMessage, Model, runtime::RuntimeMessage, util::controls::ControlCluster};
-use crossterm::event::Event;
-use items::{Item, ZoomLevel};
+use std::{iter::repeat_n, ops::Range, time::Duration};
+
+use cog_core::{
+    AppMessage, Model,
+    animation::{Easing, Sprite, Tween},
+    component::Component,
+    runtime::{
+        RuntimeMessage,
+        frame::FrameTiming,
+        subscription::{Subscription, SubscriptionId},
+    },
+    theme,
+    util::{
+        controls::{
+            ControlCluster,
+            sequence::{Sequence, Step},
+        },
+        hit_test,
+    },
+};
+use crossterm::event::{Event, KeyEvent, MouseButton, MouseEventKind};
+use blueprint::Blueprint;
+use items::{Item, TUNNEL_FRAMES, ZoomLevel};
+use log::{info, warn};
 use ndarray::{Array2, Dim, NdIndex, s};
 use rand::{
     Rng,
     distr::{Distribution, StandardUniform},
 };
 use ratatui::{
-    Frame,
+    Frame, layout,
     prelude::{Buffer, Rect},
     style::Style,
     text::Line,
     widgets::{Paragraph, Widget},
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     colors,
     components::store::RRStore,
-    controls::{BasicCluster, WorldCluster},
+    controls::{BasicCluster, BuildCluster, WorldCluster},
 };
 
 use super::{
@@ -28,11 +48,12 @@ use super::{
     store::Store,
 };
 
+pub mod blueprint;
 pub mod items;
 
 pub const SIZE: usize = 150;
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position(pub usize, pub usize);
 
 impl Distribution<Position> for StandardUniform {
@@ -77,9 +98,20 @@ impl Position {
             bounds(direction.1, self.1)?,
         ))
     }
+
+    /// like `move_by`, but stops at the edge of the grid instead of not moving at all
+    pub fn move_clamped(&self, direction: Direction, multiplier: usize) -> Self {
+        let bounds = |a: isize, b: usize| match a {
+            0.. => b.saturating_add(a as usize * multiplier).min(SIZE - 1),
+            ..0 => b.saturating_sub(a.unsigned_abs() * multiplier),
+        };
+
+        let direction: (isize, isize) = direction.into();
+        Position(bounds(direction.0, self.0), bounds(direction.1, self.1))
+    }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     North,
     South,
@@ -147,11 +179,83 @@ impl World {
     }
 }
 
-pub struct WorldWidget<'a>(&'a World, ZoomLevel);
+/// the cells of the world visible in an area
+struct Viewport {
+    rows: Range<usize>,
+    cols: Range<usize>,
+}
+
+impl Viewport {
+    fn new(cursor: Position, zoom: ZoomLevel, area: Rect) -> Self {
+        let Position(cur_row, cur_col) = cursor;
+
+        let zoom_n = zoom as usize;
+        let width = area.width as usize / 2 / zoom_n;
+        let height = area.height as usize / zoom_n;
+
+        let bounds = |a: usize, b: usize| {
+            let s = a.saturating_sub(b / 2).min(SIZE.saturating_sub(b));
+            let e = (a + b).min(SIZE);
+
+            s..e
+        };
+
+        Self {
+            rows: bounds(cur_row, height),
+            cols: bounds(cur_col, width),
+        }
+    }
+
+    /// maps a position relative to the rendered area back to the cell drawn there
+    fn cell_at(&self, zoom: ZoomLevel, area: Rect, relative: layout::Position) -> Option<Position> {
+        let zoom_n = zoom as usize;
+        let cell_width = 2 * zoom_n;
+
+        // lines are centered the same way as `Paragraph::centered`
+        let line_width = self.cols.len() * cell_width;
+        let left = (area.width as usize / 2).saturating_sub(line_width / 2);
+
+        let column = (relative.x as usize).checked_sub(left)? / cell_width;
+        let row = relative.y as usize / zoom_n;
+
+        let position = Position(self.rows.start + row, self.cols.start + column);
+        if self.rows.contains(&position.0) && self.cols.contains(&position.1) {
+            Some(position)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct WorldWidget<'a> {
+    world: &'a World,
+    zoom: ZoomLevel,
+    cursor: Position,
+    /// the cell the view is centered on, which trails the cursor while panning
+    camera: Position,
+    /// frame of animated items
+    frame: usize,
+}
 
 impl<'a> WorldWidget<'a> {
-    fn new(world: &'a World, zoom: ZoomLevel) -> Self {
-        Self(world, zoom)
+    fn new(world: &'a World, zoom: ZoomLevel, cursor: Position) -> Self {
+        Self {
+            world,
+            zoom,
+            cursor,
+            camera: cursor,
+            frame: 0,
+        }
+    }
+
+    fn camera(mut self, camera: Position) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    fn frame(mut self, frame: usize) -> Self {
+        self.frame = frame;
+        self
     }
 }
 
@@ -160,28 +264,15 @@ impl<'a> Widget for WorldWidget<'a> {
     where
         Self: Sized,
     {
-        let Position(cur_row, cur_col) = self.0.cursor;
-
-        let zoom_n = self.1 as usize;
-        let width = area.width as usize / 2 / zoom_n;
-        let height = area.height as usize / zoom_n;
-
-        let ((cursor_row, cursor_col), viewport) = {
-            let bounds = |a: usize, b: usize| {
-                let s = a.saturating_sub(b / 2).min(SIZE.saturating_sub(b));
-                let e = (a + b).min(SIZE);
+        let Position(cur_row, cur_col) = self.cursor;
+        let zoom_n = self.zoom as usize;
 
-                (s, e)
-            };
-
-            let (rs, re) = bounds(cur_row, height);
-            let (cs, ce) = bounds(cur_col, width);
-
-            (
-                (cur_row - rs, cur_col - cs),
-                self.0.grid.slice(s![rs..re, cs..ce]),
-            )
-        };
+        let Viewport { rows, cols } = Viewport::new(self.camera, self.zoom, area);
+        // the cursor may be off screen while the camera catches up
+        let cursor = cur_row
+            .checked_sub(rows.start)
+            .zip(cur_col.checked_sub(cols.start));
+        let viewport = self.world.grid.slice(s![rows, cols]);
 
         let lines: Vec<_> = viewport
             .rows()
@@ -191,9 +282,9 @@ impl<'a> Widget for WorldWidget<'a> {
                 let mut lines: Vec<_> = repeat_n(Line::default(), zoom_n).collect();
 
                 for (c, cell) in row.into_iter().enumerate() {
-                    let mut text = cell.render(self.1);
-                    if r == cursor_row && c == cursor_col {
-                        text = text.patch_style(Style::new().bg(colors::ACCENT));
+                    let mut text = cell.render(self.zoom, self.frame);
+                    if cursor == Some((r, c)) {
+                        text = text.patch_style(theme::highlight(colors::accent()));
                     }
 
                     for (i, line) in text.lines.into_iter().enumerate() {
@@ -212,12 +303,49 @@ impl<'a> Widget for WorldWidget<'a> {
     }
 }
 
+const SEQUENCE: SubscriptionId = "sequence";
+const FLOW_ID: SubscriptionId = "flow";
+
+/// how long the camera takes to catch up with the cursor
+const PAN: Duration = Duration::from_millis(150);
+/// how long each frame of a tunnel is shown
+const FLOW: Duration = Duration::from_millis(300);
+
 #[derive(Debug)]
-pub enum WorldMessage {}
+pub enum WorldMessage {
+    /// the pending key sequence timed out
+    Expire,
+    /// tunnels move on to their next frame
+    Flow,
+}
+
+enum WorldAction {
+    Basic(BasicCluster),
+    World(WorldCluster),
+    Build(BuildCluster),
+}
+
+impl From<BuildCluster> for Direction {
+    fn from(build: BuildCluster) -> Self {
+        match build {
+            BuildCluster::TunnelLeft => Direction::East,
+            BuildCluster::TunnelRight => Direction::West,
+            BuildCluster::TunnelUp => Direction::North,
+            BuildCluster::TunnelDown => Direction::South,
+        }
+    }
+}
 
 pub struct WorldModel {
     store: RRStore,
     zoom: ZoomLevel,
+    area: Rect,
+    sequence: Sequence,
+    /// this view's own cursor, the player being moved instead if there is none
+    cursor: Option<Position>,
+    /// the row and column the view is centered on, set on the first frame
+    camera: Option<Tween<(f32, f32)>>,
+    flow: Sprite<usize>,
 }
 
 impl WorldModel {
@@ -225,11 +353,177 @@ impl WorldModel {
         Self {
             store,
             zoom: ZoomLevel::Close,
+            area: Rect::default(),
+            sequence: Sequence::default(),
+            cursor: None,
+            camera: None,
+            flow: Sprite::new(0..TUNNEL_FRAMES, FLOW),
+        }
+    }
+
+    /// gives this view its own cursor starting on the player, for when several views share a store
+    pub fn detached(mut self) -> Self {
+        self.cursor = Some(self.store.borrow().world.cursor);
+        self
+    }
+
+    fn cursor(cursor: Option<Position>, store: &Store) -> Position {
+        cursor.unwrap_or(store.world.cursor)
+    }
+
+    /// the cell the view is centered on, the cursor until the first frame
+    fn camera(&self, store: &Store) -> Position {
+        match &self.camera {
+            Some(camera) => {
+                let (row, col) = camera.value();
+                Position(row.round() as usize, col.round() as usize)
+            }
+            None => Self::cursor(self.cursor, store),
+        }
+    }
+
+    /// starts the timers the world needs, to be sent once the runtime starts
+    pub fn init() -> RuntimeMessage<WorldMessage> {
+        RuntimeMessage::Subscribe(FLOW_ID, Subscription::interval(FLOW, || WorldMessage::Flow))
+    }
+
+    fn move_cursor(cursor: &mut Option<Position>, store: &mut Store, position: Position) {
+        if let Some(cursor) = cursor {
+            *cursor = position;
+            return;
+        }
+        store.world.cursor = position;
+
+        let (_, player) =
+            get_player::<&mut Position>(&mut store.entities).expect("player should exist");
+        *player = position;
+    }
+
+    /// feeds a key press to the pending sequence, returning `None` if it wasn't bound
+    fn key(&mut self, event: &KeyEvent) -> Option<RuntimeMessage<WorldMessage>> {
+        let step = self.sequence.feed(event, |keys| {
+            BasicCluster::matches(keys)
+                .map(WorldAction::Basic)
+                .or(WorldCluster::matches(keys).map(WorldAction::World))
+                .or(BuildCluster::matches(keys).map(WorldAction::Build))
+        });
+
+        match step {
+            Step::Complete(action, count) => self.perform(action, count),
+            Step::Unmatched => return None,
+            Step::Pending | Step::Cancelled => (),
+        }
+
+        // each key restarts the timeout
+        Some(if self.sequence.is_pending() {
+            RuntimeMessage::Subscribe(
+                SEQUENCE,
+                Subscription::timeout(self.sequence.timeout(), || WorldMessage::Expire),
+            )
+        } else {
+            RuntimeMessage::Unsubscribe(SEQUENCE)
+        })
+    }
+
+    fn perform(&mut self, action: WorldAction, count: usize) {
+        let mut store = self.store.borrow_mut();
+        let cursor = Self::cursor(self.cursor, &store);
+        let direction = match action {
+            WorldAction::Basic(BasicCluster::Left) => Some(Direction::East),
+            WorldAction::Basic(BasicCluster::Right) => Some(Direction::West),
+            WorldAction::Basic(BasicCluster::Up) => Some(Direction::North),
+            WorldAction::Basic(BasicCluster::Down) => Some(Direction::South),
+            WorldAction::Basic(BasicCluster::Select) => {
+                Self::handle_select(&mut store, cursor);
+                None
+            }
+            WorldAction::World(WorldCluster::ZoomIn) => {
+                if let ZoomLevel::Far = self.zoom {
+                    self.zoom = ZoomLevel::Close;
+                }
+                None
+            }
+            WorldAction::World(WorldCluster::ZoomOut) => {
+                if let ZoomLevel::Close = self.zoom {
+                    self.zoom = ZoomLevel::Far;
+                }
+                None
+            }
+            WorldAction::Build(build) => {
+                Self::place_tunnel(&mut store, cursor, build.into());
+                None
+            }
+        };
+
+        if let Some(position) = direction.map(|d| cursor.move_clamped(d, count)) {
+            Self::move_cursor(&mut self.cursor, &mut store, position);
+        }
+    }
+
+    /// places a tunnel from the player's inventory at the cursor if the cell is empty,
+    /// returning whether it was placed
+    fn place_tunnel(store: &mut Store, cursor: Position, direction: Direction) -> bool {
+        if store.world.grid[cursor] != Item::Empty {
+            return false;
+        }
+
+        let (_, inventory) = get_player::<&mut Box<dyn Inventory>>(&mut store.entities)
+            .expect("player should exist");
+
+        let slots = inventory.slots();
+        let tunnel = inventory
+            .preferred()
+            .map(|slot| slots[slot as usize])
+            .into_iter()
+            .chain(slots.iter().copied())
+            .map(|(item, _)| *item)
+            .find(|item| matches!(item, Item::Tunnel(_)));
+
+        let Some((op, ..)) = tunnel
+            .and_then(|item| inventory.prepare(PrepareOperation::Remove(Some(item), Some(1))))
+        else {
+            return false;
+        };
+        inventory.modify(op.clone());
+
+        if let Some(&entity) = op.item.entity()
+            && let Err(err) = store.entities.insert(entity, (cursor, direction))
+        {
+            warn!("placed tunnel {:?} has no entity: {}", entity, err);
         }
+        store.world.place(op.item, cursor);
+        true
+    }
+
+    /// places as much of a pasted blueprint as the inventory has tunnels for, from the cursor
+    fn paste(&self, text: &str) {
+        let blueprint: Blueprint = match text.parse() {
+            Ok(blueprint) => blueprint,
+            Err(err) => {
+                warn!("could not paste blueprint: {}", err);
+                return;
+            }
+        };
+
+        let mut store = self.store.borrow_mut();
+        let Position(row, col) = Self::cursor(self.cursor, &store);
+        let placed = blueprint
+            .tunnels
+            .iter()
+            .filter(|(r, c, _)| row + r < SIZE && col + c < SIZE)
+            .filter(|(r, c, direction)| {
+                Self::place_tunnel(&mut store, Position(row + r, col + c), *direction)
+            })
+            .count();
+
+        info!(
+            "placed {} of {} tunnels from blueprint",
+            placed,
+            blueprint.tunnels.len()
+        );
     }
 
-    fn handle_select(store: &mut Store) {
-        let cursor = store.world.cursor;
+    fn handle_select(store: &mut Store, cursor: Position) {
         let cursor_item = store.world.grid[cursor];
 
         let (_, inventory) = get_player::<&mut Box<dyn Inventory>>(&mut store.entities)
@@ -260,60 +554,381 @@ impl WorldModel {
 
 impl Model<WorldMessage> for WorldModel {
     fn view(&mut self, frame: &mut Frame) {
-        WorldWidget::new(&self.store.borrow().world, self.zoom)
-            .render(frame.area(), frame.buffer_mut());
+        self.area = frame.area();
+        let store = self.store.borrow();
+        WorldWidget::new(&store.world, self.zoom, Self::cursor(self.cursor, &store))
+            .camera(self.camera(&store))
+            .frame(*self.flow.current())
+            .render(self.area, frame.buffer_mut());
+
+        if let Some(pending) = self.sequence.pending() {
+            let area = self.area;
+            let line = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1);
+            Line::from(pending)
+                .right_aligned()
+                .style(Style::new().fg(colors::accent()))
+                .render(line.intersection(area), frame.buffer_mut());
+        }
     }
 
     fn update(&mut self, message: AppMessage<WorldMessage>) -> RuntimeMessage<WorldMessage> {
-        let mut store = self.store.borrow_mut();
-
         match message {
             AppMessage::Event(Event::Key(event)) => {
-                let w = &mut store.world;
-                let mut new_position = None;
-                match BasicCluster::contains(&event) {
-                    Some(BasicCluster::Left) => {
-                        let _ = new_position.insert(w.cursor.move_by(Direction::East, 1));
-                    }
-                    Some(BasicCluster::Right) => {
-                        let _ = new_position.insert(w.cursor.move_by(Direction::West, 1));
-                    }
-                    Some(BasicCluster::Up) => {
-                        let _ = new_position.insert(w.cursor.move_by(Direction::North, 1));
-                    }
-                    Some(BasicCluster::Down) => {
-                        let _ = new_position.insert(w.cursor.move_by(Direction::South, 1));
+                return self.key(&event).unwrap_or(RuntimeMessage::Empty);
+            }
+            AppMessage::Event(Event::Mouse(event)) => {
+                let mut store = self.store.borrow_mut();
+                if let MouseEventKind::Down(MouseButton::Left) = event.kind {
+                    let area = self.area;
+                    if let Some(position) = hit_test(area, &event).and_then(|relative| {
+                        Viewport::new(self.camera(&store), self.zoom, area)
+                            .cell_at(self.zoom, area, relative)
+                    }) {
+                        Self::move_cursor(&mut self.cursor, &mut store, position);
                     }
-                    Some(BasicCluster::Select) => Self::handle_select(&mut store),
-                    None => (),
                 }
+            }
+            AppMessage::Event(Event::Paste(text)) => self.paste(&text),
+            AppMessage::App(WorldMessage::Expire) => self.sequence.expire(),
+            AppMessage::App(WorldMessage::Flow) => {
+                self.flow.advance(FLOW);
+            }
+            _ => (),
+        };
 
-                if let Some(np) = new_position.flatten() {
-                    store.world.cursor = np;
+        RuntimeMessage::Empty
+    }
 
-                    let (_, position) = get_player::<&mut Position>(&mut store.entities)
-                        .expect("player should exist");
-                    *position = np;
-                }
+    /// pans the camera towards the cursor, requesting frames until it catches up
+    fn on_frame(&mut self, timing: &FrameTiming) -> bool {
+        let Position(row, col) = Self::cursor(self.cursor, &self.store.borrow());
+        let target = (row as f32, col as f32);
 
-                match WorldCluster::contains(&event) {
-                    Some(WorldCluster::ZoomIn) => {
-                        if let ZoomLevel::Far = self.zoom {
-                            self.zoom = ZoomLevel::Close;
-                        }
-                    }
-                    Some(WorldCluster::ZoomOut) => {
-                        if let ZoomLevel::Close = self.zoom {
-                            self.zoom = ZoomLevel::Far;
-                        }
-                    }
-                    Some(WorldCluster::Interact) => todo!(),
-                    None => (),
-                }
+        let camera = self
+            .camera
+            .get_or_insert_with(|| Tween::at(target, PAN).easing(Easing::OutQuad));
+        if *camera.target() != target {
+            camera.retarget(target);
+        }
+
+        camera.advance(timing.delta)
+    }
+}
+
+impl Component<WorldMessage> for WorldModel {
+    fn event(&mut self, event: &Event) -> Option<RuntimeMessage<WorldMessage>> {
+        match event {
+            Event::Key(event) => self.key(event),
+            Event::Mouse(_) | Event::Paste(_) => {
+                Some(self.update(AppMessage::Event(event.clone())))
             }
-            _ => (),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use cog_core::{
+        runtime::headless::{headless, headless_timed},
+        testing::{assert_snapshot, render_widget},
+        theme::ColorDepth,
+    };
+    use crossterm::event::{KeyCode, KeyModifiers, MouseEvent};
+    use futures::executor::block_on;
+    use ratatui::{backend::TestBackend, style::Modifier};
+
+    use super::*;
+    use crate::components::entity::tunnel::tunnel_builder;
+
+    fn keys(keys: &str) -> Vec<Event> {
+        keys.chars()
+            .map(|c| Event::Key(KeyEvent::from(KeyCode::Char(c))))
+            .collect()
+    }
+
+    #[test]
+    fn test_cursor() {
+        let _themes = colors::builtin(ColorDepth::TrueColor).install_local();
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        let Position(row, col) = store.borrow().world.cursor;
+
+        let mut model = WorldModel::new(store.clone());
+        let buffers = block_on(headless(
+            &mut model,
+            [Event::Key(KeyEvent::from(KeyCode::Char('j')))],
+            TestBackend::new(40, 20),
+        ))
+        .expect("headless run should succeed");
+
+        assert_eq!(
+            store.borrow().world.cursor,
+            Position((row + 1).min(SIZE - 1), col),
+            "cursor should move down"
+        );
+        assert!(
+            buffers[0]
+                .content()
+                .iter()
+                .any(|cell| cell.bg == colors::accent()),
+            "cursor should be highlighted"
+        );
+    }
+
+    #[test]
+    fn test_click() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        let area = Rect::new(0, 0, 40, 20);
+        let viewport = Viewport::new(store.borrow().world.cursor, ZoomLevel::Close, area);
+
+        let mut model = WorldModel::new(store.clone());
+        block_on(headless(
+            &mut model,
+            [Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 0,
+                row: 0,
+                modifiers: KeyModifiers::NONE,
+            })],
+            TestBackend::new(area.width, area.height),
+        ))
+        .expect("headless run should succeed");
+
+        assert_eq!(
+            store.borrow().world.cursor,
+            Position(viewport.rows.start, viewport.cols.start),
+            "cursor should move to the clicked cell"
+        );
+    }
+
+    #[test]
+    fn test_count() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        WorldModel::move_cursor(&mut None, &mut store.borrow_mut(), Position(10, 10));
+
+        let mut model = WorldModel::new(store.clone());
+        block_on(headless(&mut model, keys("5l"), TestBackend::new(40, 20)))
+            .expect("headless run should succeed");
+
+        assert_eq!(
+            store.borrow().world.cursor,
+            Position(10, 15),
+            "count should repeat the movement"
+        );
+    }
+
+    #[test]
+    fn test_count_clamped() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        WorldModel::move_cursor(&mut None, &mut store.borrow_mut(), Position(10, SIZE - 10));
+
+        let mut model = WorldModel::new(store.clone());
+        block_on(headless(
+            &mut model,
+            keys("50l20k"),
+            TestBackend::new(40, 20),
+        ))
+        .expect("headless run should succeed");
+
+        assert_eq!(
+            store.borrow().world.cursor,
+            Position(0, SIZE - 1),
+            "counts past the edge should stop at it"
+        );
+    }
+
+    #[test]
+    fn test_camera() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        WorldModel::move_cursor(&mut None, &mut store.borrow_mut(), Position(50, 50));
+
+        let mut model = WorldModel::new(store.clone());
+        block_on(headless(&mut model, keys("5l"), TestBackend::new(40, 20)))
+            .expect("headless run should succeed");
+        assert_eq!(
+            model.camera(&store.borrow()),
+            Position(50, 50),
+            "camera should trail the cursor"
+        );
+
+        block_on(headless_timed(
+            &mut model,
+            [(PAN, Event::FocusGained)],
+            TestBackend::new(40, 20),
+        ))
+        .expect("headless run should succeed");
+        assert_eq!(
+            model.camera(&store.borrow()),
+            Position(50, 55),
+            "camera should catch up once panned"
+        );
+    }
+
+    #[test]
+    fn test_flow() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        let mut model = WorldModel::new(store);
+        block_on(headless(
+            &mut model,
+            [Event::FocusGained],
+            TestBackend::new(40, 20),
+        ))
+        .expect("headless run should succeed");
+
+        let timing = FrameTiming {
+            frame: 1,
+            delta: PAN,
+            elapsed: PAN,
+            queue: Default::default(),
         };
+        assert!(
+            !model.on_frame(&timing),
+            "an idle world shouldn't request frames"
+        );
+
+        model.update(AppMessage::App(WorldMessage::Flow));
+        assert_eq!(
+            *model.flow.current(),
+            1,
+            "tunnels should flow on their timer"
+        );
+    }
 
-        RuntimeMessage::Empty
+    #[test]
+    fn test_detached() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        WorldModel::move_cursor(&mut None, &mut store.borrow_mut(), Position(10, 10));
+
+        let mut model = WorldModel::new(store.clone()).detached();
+        block_on(headless(&mut model, keys("ll"), TestBackend::new(40, 20)))
+            .expect("headless run should succeed");
+
+        assert_eq!(model.cursor, Some(Position(10, 12)));
+        assert_eq!(
+            store.borrow().world.cursor,
+            Position(10, 10),
+            "a detached view shouldn't move the player"
+        );
+    }
+
+    #[test]
+    fn test_place_tunnel() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        let cursor = {
+            let mut store = store.borrow_mut();
+            let cursor = store.world.cursor;
+            store.world.destroy(cursor);
+
+            let tunnel = store
+                .entities
+                .spawn(tunnel_builder(Direction::North, cursor).build());
+            let _ = store.entities.remove_one::<Position>(tunnel);
+            let (_, inventory) = get_player::<&mut Box<dyn Inventory>>(&mut store.entities)
+                .expect("player should exist");
+            let (op, ..) = inventory
+                .prepare(PrepareOperation::Add(Item::Tunnel(tunnel), 1))
+                .expect("inventory should have room");
+            inventory.modify(op);
+
+            cursor
+        };
+
+        let mut model = WorldModel::new(store.clone());
+        let buffers = block_on(headless(&mut model, keys("tr"), TestBackend::new(40, 20)))
+            .expect("headless run should succeed");
+
+        assert!(
+            buffers[0]
+                .content()
+                .iter()
+                .any(|cell| cell.symbol() == "t"),
+            "pending sequence should be displayed"
+        );
+
+        let mut store = store.borrow_mut();
+        let Item::Tunnel(tunnel) = store.world.grid[cursor] else {
+            panic!("tunnel should be placed at the cursor");
+        };
+        let direction = *store
+            .entities
+            .query_one_mut::<&Direction>(tunnel)
+            .expect("tunnel should have a direction");
+        assert_eq!(direction, Direction::West, "tunnel should face right");
+    }
+
+    #[test]
+    fn test_paste() {
+        let store = Rc::new(RefCell::new(Store::new(44)));
+        {
+            let mut store = store.borrow_mut();
+            WorldModel::move_cursor(&mut None, &mut store, Position(10, 10));
+            for cell in [Position(10, 10), Position(10, 11)] {
+                store.world.destroy(cell);
+
+                let tunnel = store
+                    .entities
+                    .spawn(tunnel_builder(Direction::North, cell).build());
+                let _ = store.entities.remove_one::<Position>(tunnel);
+                let (_, inventory) = get_player::<&mut Box<dyn Inventory>>(&mut store.entities)
+                    .expect("player should exist");
+                let (op, ..) = inventory
+                    .prepare(PrepareOperation::Add(Item::Tunnel(tunnel), 1))
+                    .expect("inventory should have room");
+                inventory.modify(op);
+            }
+        }
+
+        let mut model = WorldModel::new(store.clone());
+        block_on(headless(
+            &mut model,
+            [Event::Paste(">v".to_string())],
+            TestBackend::new(40, 20),
+        ))
+        .expect("headless run should succeed");
+
+        let mut store = store.borrow_mut();
+        for (cell, facing) in [
+            (Position(10, 10), Direction::West),
+            (Position(10, 11), Direction::South),
+        ] {
+            let Item::Tunnel(tunnel) = store.world.grid[cell] else {
+                panic!("tunnel should be placed at {:?}", cell);
+            };
+            let direction = *store
+                .entities
+                .query_one_mut::<&Direction>(tunnel)
+                .expect("tunnel should have a direction");
+            assert_eq!(direction, facing);
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let _themes = colors::builtin(ColorDepth::TrueColor).install_local();
+        let store = Store::new(44);
+
+        for (name, zoom) in [("world_close", ZoomLevel::Close), ("world_far", ZoomLevel::Far)] {
+            let widget = WorldWidget::new(&store.world, zoom, store.world.cursor);
+            assert_snapshot(name, &render_widget(widget, 40, 12));
+        }
+    }
+    #[test]
+    fn test_mono() {
+        let _themes = colors::builtin(ColorDepth::Mono).install_local();
+        let store = Store::new(44);
+
+        for zoom in [ZoomLevel::Close, ZoomLevel::Far] {
+            let widget = WorldWidget::new(&store.world, zoom, store.world.cursor);
+            let buffer = render_widget(widget, 40, 12);
+            assert!(
+                buffer
+                    .content()
+                    .iter()
+                    .any(|cell| cell.modifier.contains(Modifier::REVERSED)),
+                "highlights should stay visible without colors"
+            );
+        }
     }
 }
@@ -0,0 +1,78 @@
+use std::str::FromStr;
+
+use eyre::{eyre, Report};
+
+use super::Direction;
+
+/// tunnels to place relative to the cursor, written as text such as
+/// ```text
+/// >>v
+/// ..v
+/// ```
+/// where each arrow is a tunnel facing that way and `.` or a space leaves the cell alone
+#[derive(Debug, PartialEq)]
+pub struct Blueprint {
+    /// rows and columns from the top left, along with the way each tunnel faces
+    pub tunnels: Vec<(usize, usize, Direction)>,
+}
+
+impl FromStr for Blueprint {
+    type Err = Report;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut tunnels = Vec::new();
+        for (row, line) in text.trim_matches('\n').lines().enumerate() {
+            for (col, c) in line.trim_end().chars().enumerate() {
+                let direction = match c {
+                    '.' | ' ' => continue,
+                    // the same way round as the build controls
+                    '>' => Direction::West,
+                    '<' => Direction::East,
+                    '^' => Direction::North,
+                    'v' => Direction::South,
+                    c => {
+                        return Err(eyre!(
+                            "unknown blueprint cell {:?} at line {}, column {}",
+                            c,
+                            row + 1,
+                            col + 1
+                        ));
+                    }
+                };
+                tunnels.push((row, col, direction));
+            }
+        }
+
+        if tunnels.is_empty() {
+            return Err(eyre!("blueprint has no tunnels"));
+        }
+
+        Ok(Self { tunnels })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let blueprint: Blueprint = ">.v\n  <\n".parse().unwrap();
+        assert_eq!(
+            blueprint.tunnels,
+            [
+                (0, 0, Direction::West),
+                (0, 2, Direction::South),
+                (1, 2, Direction::East)
+            ]
+        );
+
+        let err = ">x".parse::<Blueprint>().expect_err("x isn't a cell");
+        assert!(
+            err.to_string().contains("line 1, column 2"),
+            "error should point at the cell: {}",
+            err
+        );
+        assert!("..\n".parse::<Blueprint>().is_err());
+    }
+}
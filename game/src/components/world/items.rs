@@ -1,10 +1,12 @@
 use std::fmt;
 
+use cog_core::theme;
 use hecs::Entity;
-use ratatui::{
-    style::{Color, Style},
-    text::Text,
-};
+use ratatui::{style::Color, text::Text};
+use serde::{Deserialize, Serialize};
+
+/// frames tunnels alternate between, so items look like they're flowing through them
+pub const TUNNEL_FRAMES: usize = 2;
 
 #[derive(Clone, Copy)]
 pub enum ZoomLevel {
@@ -12,7 +14,7 @@ pub enum ZoomLevel {
     Far = 1,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Item {
     #[default]
     Empty,
@@ -26,6 +28,7 @@ pub enum Item {
     Pod(Entity),
     Tunnel(Entity),
     Pusher(Entity),
+    Processor(Entity),
 }
 
 impl fmt::Display for Item {
@@ -40,6 +43,7 @@ impl fmt::Display for Item {
             Self::Pod(_) => write!(f, "Pod"),
             Self::Tunnel(_) => write!(f, "Tunnel"),
             Self::Pusher(_) => write!(f, "Pusher"),
+            Self::Processor(_) => write!(f, "Processor"),
         }
     }
 }
@@ -56,26 +60,42 @@ impl Item {
             Item::Pod(e) => Some(e),
             Item::Tunnel(e) => Some(e),
             Item::Pusher(e) => Some(e),
+            Item::Processor(e) => Some(e),
         }
     }
 
+    /// maps the entity held by this item, if any
+    pub fn remap(self, f: impl FnOnce(Entity) -> Option<Entity>) -> Option<Self> {
+        Some(match self {
+            Item::Pod(e) => Item::Pod(f(e)?),
+            Item::Tunnel(e) => Item::Tunnel(f(e)?),
+            Item::Pusher(e) => Item::Pusher(f(e)?),
+            Item::Processor(e) => Item::Processor(f(e)?),
+            item => item,
+        })
+    }
+
     pub fn color(&self) -> Color {
-        match self {
-            Self::Empty => Color::Reset,
-            Self::RawIron => Color::DarkGray,
-            Self::RawCopper => Color::Yellow,
-            Self::RawGold => Color::LightYellow,
-            Self::RawSilver => Color::Gray,
-            Self::RawTin => Color::LightBlue,
-            Self::Pod(_) => Color::DarkGray,
-            Self::Tunnel(_) => Color::White,
-            Self::Pusher(_) => Color::White,
-        }
+        let name = match self {
+            Self::Empty => return Color::Reset,
+            Self::RawIron => "item.iron",
+            Self::RawCopper => "item.copper",
+            Self::RawGold => "item.gold",
+            Self::RawSilver => "item.silver",
+            Self::RawTin => "item.tin",
+            Self::Pod(_) => "item.pod",
+            Self::Tunnel(_) => "item.tunnel",
+            Self::Pusher(_) => "item.pusher",
+            Self::Processor(_) => "item.processor",
+        };
+
+        theme::color(name)
     }
 
-    pub fn render(&self, zoom: ZoomLevel) -> Text {
+    /// `frame` picks the frame of animated items, such as tunnels
+    pub fn render(&self, zoom: ZoomLevel, frame: usize) -> Text {
         let color = self.color();
-        let bg = Style::default().bg(color);
+        let bg = theme::highlight(color);
 
         match zoom {
             ZoomLevel::Close => match self {
@@ -86,8 +106,11 @@ impl Item {
                 Self::RawSilver => Text::styled("┏━━┓\n┗━━┛", color),
                 Self::RawTin => Text::styled("┍━━┑\n┕━━┙", color),
                 Self::Pod(_) => Text::styled("╔══╗\n╚══╝", color),
-                Self::Tunnel(_) => Text::styled("⇅⇄⇅⇄\n⇄⇅⇄⇅", color),
+                Self::Tunnel(_) => {
+                    Text::styled(["⇅⇄⇅⇄\n⇄⇅⇄⇅", "⇄⇅⇄⇅\n⇅⇄⇅⇄"][frame % TUNNEL_FRAMES], color)
+                }
                 Self::Pusher(_) => Text::styled("PSPS\nPSPS", color),
+                Self::Processor(_) => Text::styled("┤01├\n┤10├", color),
             },
             ZoomLevel::Far => match self {
                 Self::Empty => Text::raw("  "),
@@ -97,8 +120,9 @@ impl Item {
                 Self::RawSilver => Text::styled("◇◇", bg),
                 Self::RawTin => Text::styled("◈◈", bg),
                 Self::Pod(_) => Text::styled("  ", color),
-                Self::Tunnel(_) => Text::styled("⇅⇄", color),
+                Self::Tunnel(_) => Text::styled(["⇅⇄", "⇄⇅"][frame % TUNNEL_FRAMES], color),
                 Self::Pusher(_) => Text::styled("PS", color),
+                Self::Processor(_) => Text::styled("01", color),
             },
         }
     }
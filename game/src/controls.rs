@@ -1,9 +1,11 @@
-use cog_core::{control_cluster, util::controls::ControlSet};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::{fs, path::Path};
 
-fn no_mods(code: KeyCode) -> KeyEvent {
-    KeyEvent::new(code, KeyModifiers::NONE)
-}
+use cog_core::{
+    control_cluster,
+    util::controls::{format_binding, Keymap, Overrides},
+};
+use crossterm::event::KeyEvent;
+use eyre::{eyre, Result, WrapErr};
 
 pub enum BasicCluster {
     Left,
@@ -16,73 +18,131 @@ pub enum BasicCluster {
 pub enum ActionCluster {
     Back,
     Exit,
+    Help,
+    Theme,
 }
 
 pub enum WorldCluster {
-    Interact,
     ZoomIn,
     ZoomOut,
 }
 
+pub enum BuildCluster {
+    TunnelLeft,
+    TunnelRight,
+    TunnelUp,
+    TunnelDown,
+}
+
 control_cluster!(
     BasicCluster,
-    (
-        Left,
-        ControlSet::new(&vec![
-            no_mods(KeyCode::Left),
-            no_mods(KeyCode::Char('h')),
-            no_mods(KeyCode::Char('a')),
-        ])
-    ),
-    (
-        Right,
-        ControlSet::new(&vec![
-            no_mods(KeyCode::Right),
-            no_mods(KeyCode::Char('l')),
-            no_mods(KeyCode::Char('d')),
-        ])
-    ),
-    (
-        Up,
-        ControlSet::new(&vec![
-            no_mods(KeyCode::Up),
-            no_mods(KeyCode::Char('k')),
-            no_mods(KeyCode::Char('w')),
-        ])
-    ),
-    (
-        Down,
-        ControlSet::new(&vec![
-            no_mods(KeyCode::Down),
-            no_mods(KeyCode::Char('j')),
-            no_mods(KeyCode::Char('s')),
-        ])
-    ),
-    (Select, ControlSet::new(&vec![no_mods(KeyCode::Enter)]))
+    "basic",
+    (Left, "left", "move left", ["left", "h", "a"]),
+    (Right, "right", "move right", ["right", "l", "d"]),
+    (Up, "up", "move up", ["up", "k", "w"]),
+    (Down, "down", "move down", ["down", "j", "s"]),
+    (Select, "select", "pick up or place", ["enter"])
 );
 
 control_cluster!(
     ActionCluster,
-    (
-        Back,
-        ControlSet::new(&vec![no_mods(KeyCode::Char('q')), no_mods(KeyCode::Esc)])
-    ),
-    (
-        Exit,
-        ControlSet::new(&vec![KeyEvent::new(
-            KeyCode::Char('c'),
-            KeyModifiers::CONTROL
-        )])
-    )
+    "action",
+    (Back, "back", "back", ["esc"]),
+    (Exit, "exit", "quit", ["ctrl+c", "q"]),
+    (Help, "help", "show keys", ["?"]),
+    (Theme, "theme", "switch theme", ["ctrl+t"])
 );
 
 control_cluster!(
     WorldCluster,
+    "world",
     // = is + without shift
-    (
-        Interact,
-        ControlSet::new(&vec![no_mods(KeyCode::Char(' '))])
-    ),
-    (ZoomIn, ControlSet::new(&vec![no_mods(KeyCode::Char('='))])),
-    (ZoomOut, ControlSet::new(&vec![no_mods(KeyCode::Char('-'))]))
+    (ZoomIn, "zoom_in", "zoom in", ["="]),
+    (ZoomOut, "zoom_out", "zoom out", ["-"])
+);
+
+control_cluster!(
+    BuildCluster,
+    "build",
+    (TunnelLeft, "tunnel_left", "place tunnel facing left", ["t l"]),
+    (TunnelRight, "tunnel_right", "place tunnel facing right", ["t r"]),
+    (TunnelUp, "tunnel_up", "place tunnel facing up", ["t u"]),
+    (TunnelDown, "tunnel_down", "place tunnel facing down", ["t d"])
 );
+
+/// builds the keymap from the defaults, overridden by the toml file at `path` if it exists
+///
+/// a file looks like
+/// ```toml
+/// [basic]
+/// left = ["left", "h"]
+///
+/// [action]
+/// exit = ["ctrl+c"]
+/// ```
+pub fn load_keymap(path: impl AsRef<Path>) -> Result<Keymap> {
+    let path = path.as_ref();
+    let mut keymap = Keymap::default()
+        .register::<BasicCluster>()?
+        .register::<ActionCluster>()?
+        .register::<WorldCluster>()?
+        .register::<BuildCluster>()?;
+
+    if path.exists() {
+        let overrides: Overrides = toml::from_str(&fs::read_to_string(path)?)
+            .wrap_err_with(|| format!("could not parse {}", path.display()))?;
+        keymap
+            .apply(&overrides)
+            .wrap_err_with(|| format!("invalid keymap in {}", path.display()))?;
+    }
+
+    let conflicts = keymap.conflicts();
+    if !conflicts.is_empty() {
+        let conflicts: Vec<_> = conflicts
+            .iter()
+            .map(|conflict| {
+                format!(
+                    "{} is bound to {}",
+                    format_binding(&conflict.keys),
+                    conflict.actions.join(" and ")
+                )
+            })
+            .collect();
+
+        return Err(eyre!("conflicting bindings: {}", conflicts.join(", ")));
+    }
+
+    Ok(keymap)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    #[test]
+    fn test_load_keymap() {
+        load_keymap("missing.toml").expect("default bindings shouldn't conflict");
+
+        let path = env::temp_dir().join(format!("cog_test_keymap_{}.toml", process::id()));
+        fs::write(&path, "[world]\nzoom_in = [\"q\"]\n").unwrap();
+        let err = load_keymap(&path).expect_err("q is already bound to exit");
+        assert!(
+            err.to_string()
+                .contains("q is bound to action.exit and world.zoom_in"),
+            "conflict should name both actions: {}",
+            err
+        );
+
+        fs::write(&path, "[world]\nzoom_in = [\"ctrl+nope\"]\n").unwrap();
+        let err = load_keymap(&path).expect_err("binding is invalid");
+        assert!(
+            format!("{:#}", err).contains("unknown key `nope`"),
+            "error should name the bad key: {:#}",
+            err
+        );
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,94 +1,240 @@
 use std::{
     cell::RefCell,
+    env,
     fs::OpenOptions,
     io::{stdout, Write},
+    path::Path,
     rc::Rc,
     time::Duration,
 };
 
 use cog_core::{
-    init, passthru, restore,
-    runtime::{event_loop, RuntimeMessage},
-    util::app_message,
+    component::{Component, Focus},
+    init, init_inline, passthru, restore, route,
+    runtime::{
+        clock, event_loop,
+        frame::FrameTiming,
+        remote::serve,
+        replay::{play, Recording},
+        subscription::{Subscription, SubscriptionId},
+        Options, RuntimeMessage,
+    },
+    theme,
+    util::{
+        app_message,
+        controls::{ControlCluster, Keymap},
+        help::HelpWidget,
+    },
     AppMessage, Model,
 };
 use components::{
-    entity::{get_player, tick},
-    inventory::{Inventory, InventoryWidget},
+    entity::tick,
+    inventory::{InventoryMessage, InventoryModel},
+    status::{self, StatusModel},
     store::{RRStore, Store},
     world::{WorldMessage, WorldModel},
 };
+use colors::load_themes;
+use controls::{load_keymap, ActionCluster};
 use crossterm::{
-    event::{Event, KeyCode, KeyEvent},
+    event::{Event, EventStream, KeyCode, KeyEvent},
     style::{Color, Stylize},
 };
 use env_logger::{Builder, Target};
-use eyre::Result;
-use log::Level;
-use ratatui::{layout::Rect, widgets::Widget, Frame};
+use eyre::{eyre, Result};
+use log::{error, info, Level};
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::Widget,
+    Frame,
+};
+use futures::{future::ready, FutureExt, StreamExt};
+use tokio::{net::TcpListener, signal};
 
 pub mod colors;
 pub mod components;
 pub mod controls;
 pub mod util;
 
+const TICK: SubscriptionId = "tick";
+
 #[derive(Debug)]
 enum MainMessage {
     World(WorldMessage),
+    Inventory(InventoryMessage),
     Tick,
 }
 
+/// children of the main model which can hold focus
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Screen {
+    World,
+    Inventory,
+    /// pushed on top of the others, never traversed to
+    Help,
+}
+
 struct MainModel {
     world_model: WorldModel,
+    inventory_model: InventoryModel,
     store: RRStore,
+    focus: Focus<Screen>,
+    /// whether this model advances the world, rather than only redrawing it each tick
+    ticks: bool,
+    /// the terminal lost focus, so the world is left alone until it comes back
+    paused: bool,
 }
 
 impl MainModel {
     pub fn new(store: RRStore) -> Self {
         Self {
             world_model: WorldModel::new(store.clone()),
+            inventory_model: InventoryModel::new(store.clone()),
             store,
+            focus: Focus::new([Screen::World, Screen::Inventory]),
+            ticks: true,
+            paused: false,
+        }
+    }
+
+    /// a view for a client of a served world, with its own cursor and leaving ticking to the server
+    pub fn remote(mut self) -> Self {
+        self.world_model = self.world_model.detached();
+        self.ticks = false;
+        self
+    }
+
+    /// handles key presses no focused child wanted
+    fn bubbled(&mut self, event: KeyEvent) -> RuntimeMessage<MainMessage> {
+        match (ActionCluster::contains(&event), event.code) {
+            (Some(ActionCluster::Exit), _) => return RuntimeMessage::Exit,
+            (Some(ActionCluster::Back), _) if self.focus.pop().is_none() => {
+                self.focus.focus(Screen::World);
+            }
+            (Some(ActionCluster::Back), _) => (),
+            (Some(ActionCluster::Help), _) => {
+                if self.focus.is_focused(Screen::Help) {
+                    self.focus.pop();
+                } else {
+                    self.focus.push(Screen::Help);
+                }
+            }
+            (Some(ActionCluster::Theme), _) => {
+                let name = theme::with_themes(|themes| themes.cycle().map(String::from));
+                if let Some(Some(name)) = name {
+                    info!("switched to theme {}", name);
+                }
+            }
+            (_, KeyCode::Tab) => self.focus.next(),
+            (_, KeyCode::BackTab) => self.focus.prev(),
+            _ => (),
         }
+
+        RuntimeMessage::Empty
     }
 }
 
 impl Model<MainMessage> for MainModel {
     fn view(&mut self, frame: &mut Frame) {
+        self.inventory_model
+            .set_focused(self.focus.is_focused(Screen::Inventory));
+
         self.world_model.view(frame);
+        self.inventory_model.view(frame);
 
-        let mut store = self.store.borrow_mut();
-        let (_, inventory) =
-            get_player::<&Box<dyn Inventory>>(&mut store.entities).expect("player should exist");
-
-        let height = 4;
-        let area = frame.area();
-        InventoryWidget::new(inventory.as_ref()).render(
-            Rect::new(
-                0,
-                area.height - height,
-                inventory.slots().len() as u16 * (height as f32 * 2.5) as u16,
-                height,
-            )
-            .clamp(area),
-            frame.buffer_mut(),
-        );
+        if self.paused {
+            Line::from(" paused ")
+                .right_aligned()
+                .style(
+                    Style::new()
+                        .fg(colors::accent())
+                        .add_modifier(Modifier::REVERSED),
+                )
+                .render(frame.area(), frame.buffer_mut());
+        }
+
+        if let (true, Some(keymap)) = (self.focus.is_focused(Screen::Help), Keymap::installed()) {
+            let help = HelpWidget::new(keymap).style(theme::emphasis(colors::accent()));
+            let [area] = Layout::vertical([Constraint::Length(help.height())])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::horizontal([Constraint::Length(60)])
+                .flex(Flex::Center)
+                .areas(area);
+
+            frame.render_widget(help, area);
+        }
+    }
+
+    fn on_frame(&mut self, timing: &FrameTiming) -> bool {
+        self.world_model.on_frame(timing)
     }
 
     fn update(&mut self, message: AppMessage<MainMessage>) -> RuntimeMessage<MainMessage> {
         match message {
-            AppMessage::Event(Event::Key(KeyEvent {
-                code: KeyCode::Char('q'),
-                ..
-            })) => return RuntimeMessage::Exit,
-            AppMessage::Init => app_message(MainMessage::Tick),
+            AppMessage::Event(Event::Key(key)) => route!(
+                &Event::Key(key),
+                self.focus.focused(),
+                (Screen::World, MainMessage::World, self.world_model),
+                (
+                    Screen::Inventory,
+                    MainMessage::Inventory,
+                    self.inventory_model
+                )
+            )
+            .unwrap_or_else(|| self.bubbled(key)),
+            // clicks go to whatever is under the cursor, the inventory being drawn on top
+            AppMessage::Event(event @ Event::Mouse(_)) => self
+                .inventory_model
+                .event(&event)
+                .map(|msg| msg.map(MainMessage::Inventory))
+                .or_else(|| {
+                    self.world_model
+                        .event(&event)
+                        .map(|msg| msg.map(MainMessage::World))
+                })
+                .unwrap_or(RuntimeMessage::Empty),
+            AppMessage::Event(event @ Event::Paste(_)) => match self.focus.focused() {
+                Some(Screen::World) => self
+                    .world_model
+                    .event(&event)
+                    .map(|msg| msg.map(MainMessage::World))
+                    .unwrap_or(RuntimeMessage::Empty),
+                _ => RuntimeMessage::Empty,
+            },
+            AppMessage::Event(Event::FocusLost) => {
+                self.paused = true;
+                RuntimeMessage::Empty
+            }
+            AppMessage::Event(Event::FocusGained) => {
+                self.paused = false;
+                RuntimeMessage::Empty
+            }
+            AppMessage::Init => RuntimeMessage::Batch(vec![
+                app_message(MainMessage::Tick),
+                WorldModel::init().map(MainMessage::World),
+                RuntimeMessage::Subscribe(
+                    TICK,
+                    Subscription::interval(Duration::from_secs(1), || MainMessage::Tick),
+                ),
+            ]),
             AppMessage::App(MainMessage::Tick) => {
-                tick(&mut self.store.borrow_mut());
-                RuntimeMessage::Task(Box::pin(async {
-                    tokio::time::sleep(Duration::from_secs(1)).await;
-                    app_message(MainMessage::Tick)
-                }))
+                if self.ticks && !self.paused {
+                    tick(&mut self.store.borrow_mut());
+                }
+                RuntimeMessage::Empty
+            }
+            AppMessage::Panic(task, message) => {
+                error!("task {:?} panicked: {}", task, message);
+                RuntimeMessage::Empty
             }
-            _ => passthru!(message, (MainMessage::World, self.world_model)),
+            _ => passthru!(
+                message,
+                (MainMessage::World, self.world_model),
+                (MainMessage::Inventory, self.inventory_model)
+            ),
         }
     }
 }
@@ -113,7 +259,7 @@ fn logging() -> Result<()> {
                 })
             };
 
-            let target = record.target().with(colors::PRIMARY.into()).bold();
+            let target = record.target().with(colors::primary().into()).bold();
 
             writeln!(f, "{} {}: {}", level, target, record.args())
         })
@@ -122,12 +268,147 @@ fn logging() -> Result<()> {
     Ok(())
 }
 
+const SAVE_PATH: &str = "cog.save";
+const SEED: u64 = 44;
+const KEYMAP_PATH: &str = "keymap.toml";
+const THEME_PATH: &str = "theme.toml";
+
+/// how the session was started, from the command line
+enum Mode {
+    Play,
+    /// `--record <path>`
+    Record(String),
+    /// `--replay <path>`
+    Replay(String),
+    /// `--serve <address>`, accepting telnet clients instead of using this terminal
+    Serve(String),
+    /// `--status`, a readout of the saved factory drawn under the prompt,
+    /// which keeps running while shown but is never saved, see [`StatusModel`]
+    Status,
+}
+
+impl Mode {
+    fn from_args() -> Result<Self> {
+        let mut args = env::args().skip(1);
+        let mode = match args.next().as_deref() {
+            None => Mode::Play,
+            Some("--record") => Mode::Record(args.next().ok_or(eyre!("--record needs a path"))?),
+            Some("--replay") => Mode::Replay(args.next().ok_or(eyre!("--replay needs a path"))?),
+            Some("--serve") => Mode::Serve(args.next().ok_or(eyre!("--serve needs an address"))?),
+            Some("--status") => Mode::Status,
+            Some(arg) => return Err(eyre!("unknown argument {}", arg)),
+        };
+
+        Ok(mode)
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     logging()?;
+    let mode = Mode::from_args()?;
+    load_keymap(KEYMAP_PATH)?.install()?;
+    load_themes(THEME_PATH)?.install();
+
+    // recorded sessions always start from a fresh world so they can be replayed exactly
+    let store = match mode {
+        Mode::Play | Mode::Serve(_) | Mode::Status if Path::new(SAVE_PATH).exists() => {
+            info!("loading save from {}", SAVE_PATH);
+            Store::load_file(SAVE_PATH)?
+        }
+        _ => Store::new(SEED),
+    };
+    let store = Rc::new(RefCell::new(store));
+    let model = MainModel::new(store.clone());
+
+    let mut options = Options::default().mouse(true);
+    match &mode {
+        Mode::Play => (),
+        Mode::Serve(address) => {
+            let listener = TcpListener::bind(address).await?;
+            info!("serving on {}", listener.local_addr()?);
+
+            let ticker = async {
+                let mut next = clock::now();
+                loop {
+                    clock::sleep_until(next).await;
+                    tick(&mut store.borrow_mut());
+                    next += Duration::from_secs(1);
+                }
+            };
+            let clients = serve(listener, options, |_| {
+                MainModel::new(store.clone()).remote()
+            });
+            let result = tokio::select! {
+                result = clients => result,
+                _ = ticker => Ok(()),
+                _ = signal::ctrl_c() => {
+                    info!("stopping server");
+                    Ok(())
+                }
+            };
+
+            // saved even if serving failed, so the world isn't lost with it
+            info!("saving to {}", SAVE_PATH);
+            let saved = store.borrow().save_file(SAVE_PATH);
+            return result.and(saved);
+        }
+        Mode::Status => {
+            let term = init_inline(stdout(), status::HEIGHT)?;
+            let result = event_loop(StatusModel::new(store), term, Options::default()).await;
+            return result.and(restore());
+        }
+        Mode::Record(path) => options = options.record(path),
+        Mode::Replay(path) => {
+            info!("replaying {}", path);
+            let recording = Recording::load(path)?;
+
+            // the model only sees the recording, the exit key just stops it early
+            let mut exits = EventStream::new().filter(|event| {
+                let exit = match event {
+                    Ok(Event::Key(key)) => {
+                        matches!(ActionCluster::contains(key), Some(ActionCluster::Exit))
+                    }
+                    _ => false,
+                };
+                ready(exit)
+            });
+            let exit = exits.next().map(drop);
+            let mut model = model;
+            let mut term = init(stdout())?;
+            let result = play(&mut model, &mut term, &recording, exit).await;
+            return result.and(restore());
+        }
+    }
 
-    let store = Rc::new(RefCell::new(Store::new(44)));
     let term = init(stdout())?;
-    event_loop(MainModel::new(store), term).await?;
-    restore()
+    let result = event_loop(model, term, options).await;
+    let result = result.and(restore());
+
+    // saved even if the loop failed, so progress isn't lost with it
+    if let Mode::Play = mode {
+        info!("saving to {}", SAVE_PATH);
+        let saved = store.borrow().save_file(SAVE_PATH);
+        return result.and(saved);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use cog_core::{
+        testing::{assert_snapshot, render_model},
+        theme::ColorDepth,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_snapshot() {
+        let _themes = colors::builtin(ColorDepth::TrueColor).install_local();
+        let mut model = MainModel::new(Rc::new(RefCell::new(Store::new(44))));
+
+        assert_snapshot("main", &render_model(&mut model, 80, 24));
+    }
 }